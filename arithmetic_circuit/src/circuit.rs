@@ -4,8 +4,29 @@ use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 
 use ark_ff::PrimeField;
 use std::cmp::max;
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 
+#[derive(Debug, PartialEq)]
+pub struct CircuitStats {
+    pub depth: usize,
+    pub total_gates: usize,
+    pub max_layer_width: usize,
+    pub add_gates: usize,
+    pub mul_gates: usize,
+}
+
+// Where two circuits first stop having the same structure
+#[derive(Debug, PartialEq)]
+pub enum CircuitDiff {
+    // The circuits have a different number of layers (self's, other's)
+    LayerCount(usize, usize),
+    // The layer at the given index has a different number of gates
+    LayerWidth(usize),
+    // The gate at (layer index, gate index) has different wiring or a different operation
+    Gate(usize, usize),
+}
+
 pub struct Circuit<T: PrimeField> {
     _marker: PhantomData<T>,
     layers: Vec<Vec<Gate>>,
@@ -19,6 +40,20 @@ impl<T: PrimeField> Circuit<T> {
         }
     }
 
+    // Layers are stored inputs first: layers[0] reads the circuit inputs and the last layer is the output layer.
+    // GKR walks them the other way round, so layer_idx 0 in get_add_i/get_mul_i/get_w_i is the output layer.
+    // These two constructors make the ordering of the given layers explicit; both normalise to the stored order.
+    pub fn from_layers_inputs_first(layers: Vec<Vec<Gate>>) -> Self {
+        Self::new(layers)
+    }
+
+    // layers[0] is the output layer and the last layer reads the circuit inputs
+    pub fn from_layers_output_first(mut layers: Vec<Vec<Gate>>) -> Self {
+        layers.reverse();
+
+        Self::new(layers)
+    }
+
     fn match_gate_condition(&self, gate: &Gate, condition: &Operation) -> bool {
         match gate.operation {
             Operation::Add => match condition {
@@ -32,9 +67,98 @@ impl<T: PrimeField> Circuit<T> {
         }
     }
 
+    // Replicates the sub circuit count times side by side, so copy k reads its own block of inputs and gate outputs
+    // Copy k's inputs start at k * (the sub circuit's padded input width), and its outputs at k * (its output layer's gate count)
+    // E.g. tiling a single add gate over inputs (0, 1) 3 times gives the layer [(0, 1), (2, 3), (4, 5)]
+    pub fn tile(sub_circuit: &Circuit<T>, count: usize) -> Circuit<T> {
+        let input_width = max(
+            sub_circuit
+                .layers
+                .first()
+                .into_iter()
+                .flatten()
+                .flat_map(|gate| gate.inputs.iter().map(|input| input + 1))
+                .max()
+                .unwrap_or(0)
+                .next_power_of_two(),
+            2,
+        );
+
+        let layers = sub_circuit
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(layer_idx, gates)| {
+                // the first layer reads from the inputs, every other one from the previous layer's gates
+                let block_width = match layer_idx {
+                    0 => input_width,
+                    _ => sub_circuit.layers[layer_idx - 1].len(),
+                };
+
+                (0..count)
+                    .flat_map(|copy_idx| {
+                        gates.iter().map(move |gate| {
+                            Gate::with_inputs(
+                                gate.inputs
+                                    .iter()
+                                    .map(|input| input + copy_idx * block_width)
+                                    .collect(),
+                                gate.operation.clone(),
+                            )
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Circuit::new(layers)
+    }
+
+    // Builds a binary tree circuit that pairs up adjacent values at every layer until a single output remains
+    // Layer i applies ops[i % ops.len()] to every pair, so &[Operation::Add] sums the inputs and
+    // &[Operation::Add, Operation::Mul] alternates between the two
+    // E.g. 4 inputs with [Add] => [(0, 1), (2, 3)], [(0, 1)]
+    pub fn balanced(ops: &[Operation], num_inputs: usize) -> Circuit<T> {
+        if ops.is_empty() {
+            panic!("at least one operation is needed");
+        }
+
+        if num_inputs < 2 || !num_inputs.is_power_of_two() {
+            panic!("number of inputs must be a power of two and at least 2");
+        }
+
+        let layers = (0..num_inputs.ilog2() as usize)
+            .map(|layer_idx| {
+                let layer_width = num_inputs >> (layer_idx + 1);
+
+                (0..layer_width)
+                    .map(|gate_idx| {
+                        Gate::new(
+                            2 * gate_idx,
+                            2 * gate_idx + 1,
+                            ops[layer_idx % ops.len()].clone(),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Circuit::new(layers)
+    }
+
+    // Zero-pads the inputs to a power of two (and at least 2), the same way each layer's gate outputs are padded
+    // The prover and verifier both go through this, so they agree on the input polynomial
+    pub fn pad_inputs(inputs: &[T]) -> Vec<T> {
+        let mut padded_inputs = inputs.to_vec();
+        padded_inputs.resize(max(inputs.len().next_power_of_two(), 2), T::zero());
+
+        padded_inputs
+    }
+
     // This takes in a set of inputs and for each layer of gates we have, calculate the next set of inputs
     // The set of inputs are stored as evaluation layers for easy retrieval
     pub fn evaluate_at_input(&mut self, inputs: Vec<T>) -> Vec<MultiLinearPolynomial<T>> {
+        let inputs = Self::pad_inputs(&inputs);
         let mut evaluation_layers = vec![MultiLinearPolynomial::new(&inputs)];
         let mut running_inputs = inputs;
 
@@ -43,9 +167,11 @@ impl<T: PrimeField> Circuit<T> {
                 vec![T::from(0); max(gates.len().next_power_of_two(), 2) as usize];
 
             gates.iter().enumerate().for_each(|(idx, gate)| {
+                let inputs = gate.inputs.iter().map(|input| running_inputs[*input]);
+
                 let output = match gate.operation {
-                    Operation::Add => running_inputs[gate.left] + running_inputs[gate.right],
-                    Operation::Mul => running_inputs[gate.left] * running_inputs[gate.right],
+                    Operation::Add => inputs.sum(),
+                    Operation::Mul => inputs.product(),
                 };
 
                 next_inputs[idx] = output;
@@ -58,19 +184,97 @@ impl<T: PrimeField> Circuit<T> {
         evaluation_layers
     }
 
+    // Compose the gates of every layer symbolically over the input variables and return the output in monomial form.
+    // Each monomial is (bit mask of the input variables in it, coefficient), with input i being bit i of the mask.
+    // E.g. a single add gate over inputs a, b gives [(0b01, 1), (0b10, 1)] => a + b
+    // The output returned is that of the first gate in the output layer.
+    // Limitations:
+    //   - Products reduce x * x to x, so the result is the multilinear extension of the output over boolean inputs.
+    //     For mul gates whose operands share inputs, it won't match the circuit's arithmetic on non-boolean inputs.
+    //   - The number of monomials can grow exponentially with the number of mul layers, so keep this to small circuits.
+    pub fn output_mle_coefficients(&self) -> Vec<(usize, T)> {
+        let number_of_inputs = match self.layers.first() {
+            Some(gates) => gates
+                .iter()
+                .flat_map(|gate| gate.inputs.iter().map(|input| input + 1))
+                .max()
+                .unwrap_or(0),
+            None => 0,
+        };
+
+        // every input starts out as the monomial made up of just its own variable
+        let mut running_wires: Vec<BTreeMap<usize, T>> = (0..number_of_inputs)
+            .map(|idx| BTreeMap::from([(1 << idx, T::one())]))
+            .collect();
+
+        for gates in &self.layers {
+            running_wires = gates
+                .iter()
+                .map(|gate| {
+                    // wires past the last gate are the zero padding added in evaluate_at_input
+                    let empty_wire = BTreeMap::new();
+                    let inputs = gate
+                        .inputs
+                        .iter()
+                        .map(|input| running_wires.get(*input).unwrap_or(&empty_wire));
+
+                    let mut output = match gate.operation {
+                        Operation::Add => {
+                            let mut output = BTreeMap::new();
+
+                            inputs.flatten().for_each(|(monomial, coeff)| {
+                                *output.entry(*monomial).or_insert(T::zero()) += coeff;
+                            });
+
+                            output
+                        }
+                        // multiply the inputs in one at a time, starting from the constant monomial 1
+                        Operation::Mul => inputs.fold(
+                            BTreeMap::from([(0, T::one())]),
+                            |running_product, input| {
+                                let mut output = BTreeMap::new();
+
+                                running_product
+                                    .iter()
+                                    .for_each(|(left_monomial, left_coeff)| {
+                                        input.iter().for_each(|(right_monomial, right_coeff)| {
+                                            *output
+                                                .entry(left_monomial | right_monomial)
+                                                .or_insert(T::zero()) += *left_coeff * right_coeff;
+                                        });
+                                    });
+
+                                output
+                            },
+                        ),
+                    };
+
+                    output.retain(|_, coeff| !coeff.is_zero());
+
+                    output
+                })
+                .collect();
+        }
+
+        match running_wires.first() {
+            Some(output) => output
+                .iter()
+                .map(|(monomial, coeff)| (*monomial, *coeff))
+                .collect(),
+            None => vec![],
+        }
+    }
+
     // This helps us to get the index at which a gate is present (turned on)
     // Say we have a gate that has an output at index 00, left input at 10 and right input at 11
     // The index for that in the gate poly would be 001011
     // To achieve this, we use bit manipulation - combining the left shift and OR operations.
     // Left shift to accommodate for next index to add and OR operator to add the index.
-    fn get_bit_idx(
-        &self,
-        output_idx: usize,
-        left_idx: usize,
-        right_idx: usize,
-        input_bit_repr: usize,
-    ) -> usize {
-        (((output_idx << input_bit_repr) | left_idx) << input_bit_repr) | right_idx
+    // Gates with more inputs append each of them the same way, in order
+    fn get_bit_idx(&self, output_idx: usize, input_idxs: &[usize], input_bit_repr: usize) -> usize {
+        input_idxs.iter().fold(output_idx, |bit_idx, input_idx| {
+            (bit_idx << input_bit_repr) | input_idx
+        })
     }
 
     // This gets the gate polynomial at an index represented in multilinear form
@@ -79,42 +283,79 @@ impl<T: PrimeField> Circuit<T> {
     // The evaluation points would basically be 2^(all bits used to represent output, and the two indexes).
     // i.e if we have the gate at output index 10, left input index at 00 and right index at 01:
     // In total, there are 6 bits (100001) in total used to represent this gate poly which is 2^6 evaluation points.
-    fn get_gate_poly(&self, layer_idx: usize, condition: Operation) -> MultiLinearPolynomial<T> {
+    // The number of bits the gate poly of the given layer (0 being the output layer) uses for its output index "a"
+    // and for each of its input indexes "b" and "c", i.e. add_i/mul_i have output bits + 2 * input bits variables
+    // (output bits + fan-in * input bits for layers of wider gates, see layer_fan_in)
+    pub fn gate_poly_bit_lengths(&self, layer_idx: usize) -> (usize, usize) {
         if layer_idx >= self.layers.len() {
             panic!("layer index out of bounds");
         }
 
         let gates = &self.layers[self.layers.len() - layer_idx - 1];
 
-        let mut output_length = gates.len().next_power_of_two() as usize;
+        // A single-gate layer would need 0 bits for its output index, but evaluate_at_input pads every layer to at least 2 values,
+        // so the output layer's W poly (and the random values GKR draws for it) always has at least one variable.
+        // We pad the output index to 1 bit the same way, keeping add_i/mul_i's "a" variables in line with that W poly.
+        let output_length = max(gates.len().next_power_of_two(), 2);
 
-        match output_length {
-            1 => output_length = 2 * output_length,
-            _ => (),
+        let input_bit_length = gates
+            .iter()
+            .flat_map(|gate| gate.inputs.iter().map(|input| input + 1))
+            .max()
+            .unwrap()
+            .next_power_of_two()
+            .ilog2() as usize;
+
+        (output_length.ilog2() as usize, input_bit_length)
+    }
+
+    // The number of inputs every gate of the given layer (0 being the output layer) has
+    // The gate poly encodes that many input indexes, so all the gates of a layer must agree on it.
+    // GKR's sumcheck splits add_i/mul_i into exactly a, b and c, so the GKR prover and verifier only support fan-in 2 layers;
+    // a fan-in k layer's gate poly has output bits + k * input bits variables
+    pub fn layer_fan_in(&self, layer_idx: usize) -> usize {
+        if layer_idx >= self.layers.len() {
+            panic!("layer index out of bounds");
         }
 
-        let input_lengths_vec: Vec<usize> = gates.iter().fold(vec![], |acc, gate| {
-            let mut new_acc = vec![gate.left + 1, gate.right + 1];
+        let fan_ins = self.gate_fan_ins(layer_idx);
+        let fan_in = fan_ins.first().copied().unwrap_or(2);
 
-            new_acc.extend(&acc);
+        if let Some(other) = fan_ins.iter().find(|other| **other != fan_in) {
+            panic!(
+                "gates in a layer must have the same fan-in, got {} and {}",
+                fan_in, other
+            );
+        }
 
-            new_acc
-        });
+        fan_in
+    }
+
+    // The number of inputs of each gate of the given layer (0 being the output layer), in gate order
+    // Unlike layer_fan_in this doesn't require the gates to agree, so it can inspect a circuit that hasn't been validated
+    pub fn gate_fan_ins(&self, layer_idx: usize) -> Vec<usize> {
+        if layer_idx >= self.layers.len() {
+            panic!("layer index out of bounds");
+        }
 
-        let input_bit_length = input_lengths_vec
+        self.layers[self.layers.len() - layer_idx - 1]
             .iter()
-            .max()
-            .unwrap()
-            .next_power_of_two()
-            .ilog2() as usize;
+            .map(|gate| gate.fan_in())
+            .collect()
+    }
+
+    fn get_gate_poly(&self, layer_idx: usize, condition: Operation) -> MultiLinearPolynomial<T> {
+        let (output_bit_length, input_bit_length) = self.gate_poly_bit_lengths(layer_idx);
+        let fan_in = self.layer_fan_in(layer_idx);
+        let gates = &self.layers[self.layers.len() - layer_idx - 1];
 
         let mut evaluation_points: Vec<T> =
-            vec![T::from(0); output_length * (1 << (2 * input_bit_length)) as usize];
+            vec![T::from(0); 1 << (output_bit_length + fan_in * input_bit_length)];
 
         gates.iter().enumerate().for_each(|(idx, gate)| {
             if self.match_gate_condition(&gate, &condition) {
                 // set the index where gate is present to 1.
-                evaluation_points[self.get_bit_idx(idx, gate.left, gate.right, input_bit_length)] =
+                evaluation_points[self.get_bit_idx(idx, &gate.inputs, input_bit_length)] =
                     T::from(1);
             }
         });
@@ -132,9 +373,9 @@ impl<T: PrimeField> Circuit<T> {
             panic!("layer index out of bounds");
         }
 
-        MultiLinearPolynomial::new(
-            layer_evaluations[layer_evaluations.len() - layer_idx - 1].get_evaluation_points(),
-        )
+        // the layer evaluations are only borrowed (the prover keeps using them), so the evaluations can't be moved out here;
+        // cloning the polynomial directly at least skips re-validating its length in new
+        layer_evaluations[layer_evaluations.len() - layer_idx - 1].clone()
     }
 
     pub fn get_add_i(&self, layer_idx: usize) -> MultiLinearPolynomial<T> {
@@ -145,6 +386,76 @@ impl<T: PrimeField> Circuit<T> {
         self.get_gate_poly(layer_idx, Operation::Mul)
     }
 
+    // Builds the (add_i, mul_i) gate polys of every layer at once, indexed the same way as get_add_i/get_mul_i (0 => output layer)
+    pub fn all_gate_polys(&self) -> Vec<(MultiLinearPolynomial<T>, MultiLinearPolynomial<T>)> {
+        (0..self.get_layer_count())
+            .map(|layer_idx| (self.get_add_i(layer_idx), self.get_mul_i(layer_idx)))
+            .collect()
+    }
+
+    // Get the number of evaluation points of the output layer's w polynomial
+    // This follows the padding in evaluate_at_input (next power of two, with at least 2 points)
+    pub fn output_width(&self) -> usize {
+        match self.layers.last() {
+            Some(gates) => max(gates.len().next_power_of_two(), 2),
+            None => 0,
+        }
+    }
+
+    // Gate counts and shape of the circuit, to estimate proving cost before proving
+    pub fn stats(&self) -> CircuitStats {
+        let gates = self.layers.iter().flatten();
+        let add_gates = gates
+            .clone()
+            .filter(|gate| self.match_gate_condition(gate, &Operation::Add))
+            .count();
+        let total_gates = gates.count();
+
+        CircuitStats {
+            depth: self.layers.len(),
+            total_gates,
+            max_layer_width: self
+                .layers
+                .iter()
+                .map(|gates| gates.len())
+                .max()
+                .unwrap_or(0),
+            add_gates,
+            mul_gates: total_gates - add_gates,
+        }
+    }
+
+    // Compares the layer shapes and every gate's (inputs, operation), returning the first place they differ
+    pub fn structural_diff(&self, other: &Circuit<T>) -> Option<CircuitDiff> {
+        if self.layers.len() != other.layers.len() {
+            return Some(CircuitDiff::LayerCount(
+                self.layers.len(),
+                other.layers.len(),
+            ));
+        }
+
+        for (layer_idx, (gates, other_gates)) in self.layers.iter().zip(&other.layers).enumerate() {
+            if gates.len() != other_gates.len() {
+                return Some(CircuitDiff::LayerWidth(layer_idx));
+            }
+
+            if let Some(gate_idx) = gates
+                .iter()
+                .zip(other_gates)
+                .position(|(gate, other_gate)| gate != other_gate)
+            {
+                return Some(CircuitDiff::Gate(layer_idx, gate_idx));
+            }
+        }
+
+        None
+    }
+
+    // Whether the prover and verifier's circuits describe the exact same computation
+    pub fn structural_eq(&self, other: &Circuit<T>) -> bool {
+        self.structural_diff(other).is_none()
+    }
+
     // Calculate how many layers we have in the circuit
     pub fn get_layer_count(&self) -> usize {
         self.layers.len()
@@ -181,6 +492,283 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_tile() {
+        let (_, sub_circuit) = init_circuit_and_evaluate();
+        let mut circuit = Circuit::tile(&sub_circuit, 2);
+
+        let expected_circuit: Circuit<Fq> = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Mul),
+                Gate::new(4, 5, Operation::Add),
+                Gate::new(6, 7, Operation::Mul),
+            ],
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Add),
+            ],
+        ]);
+
+        assert!(circuit.structural_eq(&expected_circuit));
+
+        // (1 + 2) + (3 * 4) and (5 + 6) + (7 * 8)
+        let outputs = circuit
+            .evaluate_at_input((1..=8).map(Fq::from).collect())
+            .pop()
+            .unwrap();
+
+        assert_eq!(
+            *outputs.get_evaluation_points(),
+            vec![Fq::from(15), Fq::from(67)]
+        );
+    }
+
+    #[test]
+    pub fn test_balanced() {
+        let circuit: Circuit<Fq> = Circuit::balanced(&[Operation::Add], 4);
+
+        assert!(circuit.structural_eq(&Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Add),
+            ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ])));
+
+        // (1 + 2) * (3 + 4) and (5 + 6) * (7 + 8), then added
+        let mut circuit: Circuit<Fq> = Circuit::balanced(&[Operation::Add, Operation::Mul], 8);
+
+        assert_eq!(circuit.get_layer_count(), 3);
+        assert_eq!(
+            *circuit
+                .evaluate_at_input((1..=8).map(Fq::from).collect())
+                .pop()
+                .unwrap()
+                .get_evaluation_points(),
+            vec![Fq::from(21 + 165), Fq::from(0)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "number of inputs must be a power of two")]
+    pub fn test_balanced_rejects_non_power_of_two_inputs() {
+        Circuit::<Fq>::balanced(&[Operation::Add], 6);
+    }
+
+    #[test]
+    pub fn test_from_layers_inputs_and_output_first() {
+        let (_, circuit) = init_circuit_and_evaluate();
+
+        let input_layer = vec![
+            Gate::new(0, 1, Operation::Add),
+            Gate::new(2, 3, Operation::Mul),
+        ];
+        let output_layer = vec![Gate::new(0, 1, Operation::Add)];
+
+        let inputs_first: Circuit<Fq> =
+            Circuit::from_layers_inputs_first(vec![input_layer.clone(), output_layer.clone()]);
+        let output_first: Circuit<Fq> =
+            Circuit::from_layers_output_first(vec![output_layer, input_layer]);
+
+        assert!(inputs_first.structural_eq(&circuit));
+        assert!(output_first.structural_eq(&circuit));
+        assert_eq!(inputs_first.all_gate_polys(), output_first.all_gate_polys());
+    }
+
+    #[test]
+    pub fn test_gate_poly_bit_lengths() {
+        let (_, circuit) = init_circuit_and_evaluate();
+
+        // output layer: 1 gate (padded to 2 outputs) over 2 inputs, input layer: 2 gates over 4 inputs
+        assert_eq!(circuit.gate_poly_bit_lengths(0), (1, 1));
+        assert_eq!(circuit.gate_poly_bit_lengths(1), (1, 2));
+
+        circuit
+            .all_gate_polys()
+            .iter()
+            .enumerate()
+            .for_each(|(layer_idx, (add_i, _))| {
+                let (output_bits, input_bits) = circuit.gate_poly_bit_lengths(layer_idx);
+
+                assert_eq!(
+                    add_i.number_of_variables() as usize,
+                    output_bits + 2 * input_bits
+                );
+            });
+    }
+
+    #[test]
+    pub fn test_gates_with_fan_in_3() {
+        let mut circuit = Circuit::new(vec![
+            vec![
+                Gate::with_inputs(vec![0, 1, 2], Operation::Add),
+                Gate::with_inputs(vec![3, 4, 5], Operation::Mul),
+            ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ]);
+
+        let inputs = (1..=6).map(Fq::from).collect::<Vec<_>>();
+        let layer_evaluations = circuit.evaluate_at_input(inputs);
+
+        // 1 + 2 + 3 and 4 * 5 * 6
+        assert_eq!(
+            *layer_evaluations[1].get_evaluation_points(),
+            vec![Fq::from(6), Fq::from(120)]
+        );
+        assert_eq!(
+            *layer_evaluations[2].get_evaluation_points(),
+            vec![Fq::from(126), Fq::from(0)]
+        );
+
+        // the input layer's gate poly encodes the output index and three input indexes of 3 bits each
+        assert_eq!(circuit.layer_fan_in(0), 2);
+        assert_eq!(circuit.layer_fan_in(1), 3);
+        assert_eq!(circuit.gate_fan_ins(1), vec![3, 3]);
+        assert_eq!(circuit.gate_poly_bit_lengths(1), (1, 3));
+
+        let turned_on_indexes = |gate_poly: MultiLinearPolynomial<Fq>| {
+            gate_poly
+                .get_evaluation_points()
+                .iter()
+                .enumerate()
+                .filter(|(_, eval)| **eval == Fq::from(1))
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(circuit.get_add_i(1).number_of_variables(), 1 + 3 * 3);
+        assert_eq!(
+            turned_on_indexes(circuit.get_add_i(1)),
+            vec![0b0_000_001_010]
+        );
+        assert_eq!(
+            turned_on_indexes(circuit.get_mul_i(1)),
+            vec![0b1_011_100_101]
+        );
+
+        // 3 input gates compose symbolically too: (a + b + c) + d * e * f
+        let mut expected_coefficients = vec![
+            (0b000001, Fq::from(1)),
+            (0b000010, Fq::from(1)),
+            (0b000100, Fq::from(1)),
+            (0b111000, Fq::from(1)),
+        ];
+        expected_coefficients.sort();
+
+        assert_eq!(circuit.output_mle_coefficients(), expected_coefficients);
+    }
+
+    #[test]
+    #[should_panic(expected = "gates in a layer must have the same fan-in, got 2 and 3")]
+    pub fn test_gate_poly_with_mixed_fan_in() {
+        let circuit: Circuit<Fq> = Circuit::new(vec![vec![
+            Gate::new(0, 1, Operation::Add),
+            Gate::with_inputs(vec![1, 2, 3], Operation::Add),
+        ]]);
+
+        // gate_fan_ins doesn't need the gates to agree
+        assert_eq!(circuit.gate_fan_ins(0), vec![2, 3]);
+
+        circuit.get_add_i(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a gate needs at least 2 inputs, got 1")]
+    pub fn test_gate_with_too_few_inputs() {
+        Gate::with_inputs(vec![0], Operation::Add);
+    }
+
+    #[test]
+    pub fn test_pad_inputs() {
+        assert_eq!(
+            Circuit::pad_inputs(&[Fq::from(1), Fq::from(2), Fq::from(3)]),
+            vec![Fq::from(1), Fq::from(2), Fq::from(3), Fq::from(0)]
+        );
+        assert_eq!(
+            Circuit::pad_inputs(&[Fq::from(7)]),
+            vec![Fq::from(7), Fq::from(0)]
+        );
+        assert_eq!(
+            Circuit::pad_inputs(&[Fq::from(1), Fq::from(2)]),
+            vec![Fq::from(1), Fq::from(2)]
+        );
+    }
+
+    #[test]
+    pub fn test_output_width() {
+        let (circuit_evaluations, circuit) = init_circuit_and_evaluate();
+
+        assert_eq!(circuit.output_width(), 2);
+        assert_eq!(
+            circuit.output_width(),
+            circuit_evaluations
+                .last()
+                .unwrap()
+                .get_evaluation_points()
+                .len()
+        );
+    }
+
+    #[test]
+    pub fn test_stats() {
+        let circuit: Circuit<Fq> = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Add),
+                Gate::new(4, 5, Operation::Add),
+                Gate::new(6, 7, Operation::Mul),
+            ],
+            vec![
+                Gate::new(0, 1, Operation::Mul),
+                Gate::new(2, 3, Operation::Add),
+            ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ]);
+
+        assert_eq!(
+            circuit.stats(),
+            CircuitStats {
+                depth: 3,
+                total_gates: 7,
+                max_layer_width: 4,
+                add_gates: 5,
+                mul_gates: 2,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_structural_diff() {
+        let (_, circuit) = init_circuit_and_evaluate();
+        let (_, same_circuit) = init_circuit_and_evaluate();
+
+        assert!(circuit.structural_eq(&same_circuit));
+        assert_eq!(circuit.structural_diff(&same_circuit), None);
+
+        let changed_circuit: Circuit<Fq> = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Add),
+            ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ]);
+
+        assert!(!circuit.structural_eq(&changed_circuit));
+        assert_eq!(
+            circuit.structural_diff(&changed_circuit),
+            Some(CircuitDiff::Gate(0, 1))
+        );
+
+        let shallower_circuit: Circuit<Fq> =
+            Circuit::new(vec![vec![Gate::new(0, 1, Operation::Add)]]);
+
+        assert_eq!(
+            circuit.structural_diff(&shallower_circuit),
+            Some(CircuitDiff::LayerCount(2, 1))
+        );
+    }
+
     #[test]
     pub fn test_get_add_i() {
         let (_, circuit) = init_circuit_and_evaluate();
@@ -200,4 +788,83 @@ mod tests {
 
         assert_eq!(*circuit.get_mul_i(1).get_evaluation_points(), result_vec);
     }
+
+    #[test]
+    pub fn test_single_gate_output_layer_gate_polys() {
+        let circuit: Circuit<Fq> = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Mul),
+            ],
+            vec![Gate::new(0, 1, Operation::Mul)],
+        ]);
+
+        // 1 (padded) bit for the output gate + 1 bit each for its two inputs
+        let (add_i, mul_i) = (circuit.get_add_i(0), circuit.get_mul_i(0));
+
+        assert_eq!(add_i.number_of_variables(), 3);
+        assert_eq!(mul_i.number_of_variables(), 3);
+        assert_eq!(
+            circuit.output_width().ilog2(),
+            mul_i.number_of_variables() - 2
+        );
+
+        // only a = 0, b = 0, c = 1 is wired, and to a mul gate
+        assert!(add_i
+            .get_evaluation_points()
+            .iter()
+            .all(|eval| *eval == Fq::from(0)));
+        assert_eq!(
+            mul_i.get_evaluation_points(),
+            &vec![
+                Fq::from(0),
+                Fq::from(1),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(0),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_all_gate_polys() {
+        let (_, circuit) = init_circuit_and_evaluate();
+
+        let gate_polys = circuit.all_gate_polys();
+
+        assert_eq!(gate_polys.len(), circuit.get_layer_count());
+
+        for (layer_idx, (add_i, mul_i)) in gate_polys.iter().enumerate() {
+            assert_eq!(*add_i, circuit.get_add_i(layer_idx));
+            assert_eq!(*mul_i, circuit.get_mul_i(layer_idx));
+        }
+    }
+
+    #[test]
+    pub fn test_output_mle_coefficients() {
+        let add_circuit: Circuit<Fq> = Circuit::new(vec![vec![Gate::new(0, 1, Operation::Add)]]);
+
+        // a + b
+        assert_eq!(
+            add_circuit.output_mle_coefficients(),
+            vec![(0b01, Fq::from(1)), (0b10, Fq::from(1))]
+        );
+
+        // (a + b) * (a + a) => 2a + 2ab over boolean inputs
+        let mul_circuit: Circuit<Fq> = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(0, 0, Operation::Add),
+            ],
+            vec![Gate::new(0, 1, Operation::Mul)],
+        ]);
+
+        assert_eq!(
+            mul_circuit.output_mle_coefficients(),
+            vec![(0b01, Fq::from(2)), (0b11, Fq::from(2))]
+        );
+    }
 }
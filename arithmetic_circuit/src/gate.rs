@@ -1,22 +1,31 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Operation {
     Add,
     Mul,
 }
 
-#[derive(Debug)]
+// A gate applies its operation across all of its inputs, e.g. an add gate over (0, 1, 2) outputs w_0 + w_1 + w_2
+#[derive(Debug, Clone, PartialEq)]
 pub struct Gate {
-    pub left: usize,
-    pub right: usize,
+    pub inputs: Vec<usize>,
     pub operation: Operation,
 }
 
 impl Gate {
     pub fn new(left: usize, right: usize, operation: Operation) -> Self {
-        Self {
-            left,
-            right,
-            operation,
+        Self::with_inputs(vec![left, right], operation)
+    }
+
+    // A gate with fan-in inputs.len(), for sums or products over more than two values without a tree of fan-in 2 gates
+    pub fn with_inputs(inputs: Vec<usize>, operation: Operation) -> Self {
+        if inputs.len() < 2 {
+            panic!("a gate needs at least 2 inputs, got {}", inputs.len());
         }
+
+        Self { inputs, operation }
+    }
+
+    pub fn fan_in(&self) -> usize {
+        self.inputs.len()
     }
 }
@@ -1,5 +1,36 @@
 use ark_ff::FftField;
 
+#[cfg(test)]
+use std::cell::Cell;
+
+// Sizes from which FftStrategy::Auto switches to the iterative FFT, which avoids the recursive one's allocations
+const ITERATIVE_FFT_THRESHOLD: usize = 256;
+
+#[cfg(test)]
+thread_local! {
+    // number of iterative FFTs run on the current thread, so tests can see which implementation got picked
+    static ITERATIVE_FFT_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FftStrategy {
+    Recursive,
+    Iterative,
+    // iterative from ITERATIVE_FFT_THRESHOLD points up, recursive below
+    Auto,
+}
+
+impl FftStrategy {
+    // The implementation this strategy runs for an input of size n
+    pub fn resolve(self, n: usize) -> FftStrategy {
+        match self {
+            FftStrategy::Auto if n >= ITERATIVE_FFT_THRESHOLD => FftStrategy::Iterative,
+            FftStrategy::Auto => FftStrategy::Recursive,
+            strategy => strategy,
+        }
+    }
+}
+
 pub struct Polynomial<T: FftField> {
     _marker: std::marker::PhantomData<T>,
 }
@@ -71,12 +102,83 @@ impl<T: FftField> Polynomial<T> {
         y
     }
 
+    // Same transform as _fft, done bottom up in place:
+    // reorder the input by bit-reversed index, so each butterfly stage combines adjacent blocks
+    // of the previous stage, doubling the block length until it covers the whole input
+    fn _fft_iterative(coefficients_or_values: &[T], is_inverse: bool) -> Vec<T> {
+        #[cfg(test)]
+        ITERATIVE_FFT_CALLS.with(|calls| calls.set(calls.get() + 1));
+
+        let n = coefficients_or_values.len();
+
+        if !n.is_power_of_two() {
+            panic!("FFT size must be a power of two");
+        }
+
+        let log_n = n.ilog2();
+        let mut y = vec![T::from(0); n];
+
+        coefficients_or_values
+            .iter()
+            .enumerate()
+            .for_each(|(idx, num)| {
+                let reversed_idx = match log_n {
+                    0 => 0,
+                    _ => idx.reverse_bits() >> (usize::BITS - log_n),
+                };
+
+                y[reversed_idx] = *num;
+            });
+
+        let mut block_length = 2;
+
+        while block_length <= n {
+            let root_of_unity = T::get_root_of_unity(block_length as u64).unwrap();
+            let w = if is_inverse {
+                root_of_unity.inverse().unwrap()
+            } else {
+                root_of_unity
+            };
+
+            for block_start in (0..n).step_by(block_length) {
+                let mut wj = T::from(1);
+
+                for j in 0..block_length / 2 {
+                    let (even, odd) = (
+                        y[block_start + j],
+                        wj * y[block_start + j + block_length / 2],
+                    );
+
+                    y[block_start + j] = even + odd;
+                    y[block_start + j + block_length / 2] = even - odd;
+                    wj *= w;
+                }
+            }
+
+            block_length *= 2;
+        }
+
+        y
+    }
+
+    fn transform(coefficients_or_values: &[T], is_inverse: bool, strategy: FftStrategy) -> Vec<T> {
+        match strategy.resolve(coefficients_or_values.len()) {
+            FftStrategy::Iterative => Self::_fft_iterative(coefficients_or_values, is_inverse),
+            _ => Self::_fft(coefficients_or_values, is_inverse),
+        }
+    }
+
     // Perform Fast Fourier Transforms to convert Polynomial to Values (Samples) Representation
     // This can be done in O(nlogn) time to perform a linear O(n) operation in Sample like evaluation that would have originally taken O(n^2) in Coefficients form
 
     // Transform to evaluation form
     pub fn convert_to_evaluations(coefficients: &[T]) -> Vec<T> {
-        Self::_fft(coefficients, false)
+        Self::convert_to_evaluations_with(coefficients, FftStrategy::Auto)
+    }
+
+    // Same as convert_to_evaluations, with an explicit choice of FFT implementation
+    pub fn convert_to_evaluations_with(coefficients: &[T], strategy: FftStrategy) -> Vec<T> {
+        Self::transform(coefficients, false, strategy)
     }
 
     // Perform inverse Fast Fourier Transform to convert Sample representation back to Coefficients
@@ -84,7 +186,12 @@ impl<T: FftField> Polynomial<T> {
 
     // Transform to Coefficient form
     pub fn convert_to_coefficents(values: &[T]) -> Vec<T> {
-        Self::_fft(&values, true)
+        Self::convert_to_coefficents_with(values, FftStrategy::Auto)
+    }
+
+    // Same as convert_to_coefficents, with an explicit choice of FFT implementation
+    pub fn convert_to_coefficents_with(values: &[T], strategy: FftStrategy) -> Vec<T> {
+        Self::transform(values, true, strategy)
             .iter()
             .map(|x| *x / T::from(values.len() as u64))
             .collect()
@@ -105,4 +212,45 @@ mod test {
 
         assert_eq!(result_coefficients, coefficients,)
     }
+
+    #[test]
+    pub fn test_fft_strategies_agree() {
+        let coefficients = (0..512u64)
+            .map(|idx| Fr::from(idx * idx + 7))
+            .collect::<Vec<_>>();
+
+        let recursive_values =
+            Polynomial::convert_to_evaluations_with(&coefficients, FftStrategy::Recursive);
+
+        assert_eq!(
+            Polynomial::convert_to_evaluations_with(&coefficients, FftStrategy::Iterative),
+            recursive_values
+        );
+        assert_eq!(
+            Polynomial::convert_to_evaluations_with(&coefficients, FftStrategy::Auto),
+            recursive_values
+        );
+        assert_eq!(
+            Polynomial::convert_to_coefficents_with(&recursive_values, FftStrategy::Iterative),
+            coefficients
+        );
+    }
+
+    #[test]
+    pub fn test_auto_strategy_picks_iterative_for_large_sizes() {
+        let iterative_calls = || ITERATIVE_FFT_CALLS.with(|calls| calls.get());
+
+        let small_coefficients = vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(1)];
+        let large_coefficients = (0..512u64).map(Fr::from).collect::<Vec<_>>();
+
+        Polynomial::convert_to_evaluations(&small_coefficients);
+        assert_eq!(iterative_calls(), 0);
+
+        Polynomial::convert_to_evaluations(&large_coefficients);
+        assert_eq!(iterative_calls(), 1);
+
+        assert_eq!(FftStrategy::Auto.resolve(4), FftStrategy::Recursive);
+        assert_eq!(FftStrategy::Auto.resolve(512), FftStrategy::Iterative);
+        assert_eq!(FftStrategy::Recursive.resolve(512), FftStrategy::Recursive);
+    }
 }
@@ -1,11 +1,36 @@
-use ark_ff::PrimeField;
+use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
+
+use ark_ff::{FftField, PrimeField};
 use sha3::digest::Update;
-use sha3::{Digest, Keccak256};
+use sha3::{Digest, Keccak256, Sha3_256, Sha3_512};
 use std::marker::PhantomData;
 
+// With the recording feature on, every transcript keeps a log of the operations performed on it,
+// so tests can check a prover and verifier drive their transcripts the exact same way. The log holds a copy
+// of every appended byte and is never trimmed, so keep the feature out of anything but test builds
+#[cfg(feature = "recording")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranscriptOperation {
+    Append(Vec<u8>),
+    SampleChallenge,
+}
+
+// from_le_bytes_mod_order reduces the bytes it's given, which skews challenges towards small values
+// drawing 16 bytes more than the modulus takes up keeps that bias below 2^-128
+pub fn challenge_byte_length<T: PrimeField>() -> usize {
+    T::MODULUS_BIT_SIZE.div_ceil(8) as usize + 16
+}
+
+// every transcript turns squeezed bytes into a challenge the same way
+pub fn challenge_from_hash<T: PrimeField>(bytes: &[u8]) -> T {
+    T::from_le_bytes_mod_order(bytes)
+}
+
 pub struct Transcript<T: PrimeField> {
     _marker: PhantomData<T>,
     hasher: Keccak256,
+    #[cfg(feature = "recording")]
+    operations: Vec<TranscriptOperation>,
 }
 
 impl<T: PrimeField> Transcript<T> {
@@ -13,11 +38,31 @@ impl<T: PrimeField> Transcript<T> {
         Transcript {
             _marker: Default::default(),
             hasher: Keccak256::new(),
+            #[cfg(feature = "recording")]
+            operations: Vec::new(),
         }
     }
 
+    #[cfg(feature = "recording")]
+    pub fn operations(&self) -> &[TranscriptOperation] {
+        &self.operations
+    }
+
+    // creates a transcript that has already absorbed a protocol tag (e.g. b"GKR")
+    // so transcripts of different protocols never start from the same state
+    pub fn with_domain(tag: &[u8]) -> Self {
+        let mut transcript = Self::new();
+        transcript.append(tag);
+
+        transcript
+    }
+
     // update current hasher state with new data
     pub fn append(&mut self, data: &[u8]) {
+        #[cfg(feature = "recording")]
+        self.operations
+            .push(TranscriptOperation::Append(data.to_vec()));
+
         Update::update(&mut self.hasher, data);
     }
 
@@ -25,14 +70,45 @@ impl<T: PrimeField> Transcript<T> {
         data.iter().for_each(|f| self.append(*f));
     }
 
+    // absorb a count or length as exactly 8 little-endian bytes, so every protocol binds lengths the same way
+    pub fn append_u64(&mut self, n: u64) {
+        self.append(&n.to_le_bytes());
+    }
+
+    // absorb an unordered set of items: they're sorted before absorbing, so any ordering of the same items gives the same challenge
+    // The number of items and each item's length are bound too, so items can't be split or merged without changing the challenge
+    // Only use this when the order really carries no meaning (e.g. a batch of leaves the verifier looks up by value);
+    // whenever a position matters (round polynomials, layer roots, indexed openings) use append, or reordered data goes unnoticed
+    pub fn append_set(&mut self, items: &[Vec<u8>]) {
+        let mut sorted_items = items.to_vec();
+        sorted_items.sort();
+
+        self.append_u64(sorted_items.len() as u64);
+
+        sorted_items.iter().for_each(|item| {
+            self.append_u64(item.len() as u64);
+            self.append(item);
+        });
+    }
+
+    // absorb a multilinear polynomial, binding its number of variables before its evaluations
+    // so polynomials with different variable counts can't alias through their flattened bytes
+    pub fn append_multilinear(&mut self, polynomial: &MultiLinearPolynomial<T>) {
+        self.append_u64(polynomial.number_of_variables() as u64);
+        self.append(&polynomial.to_bytes());
+    }
+
     pub fn sample_challenge(&mut self) -> T {
         // uses the current hasher and generates a field value from it
-        let hash_result = self.hasher.clone().finalize();
+        let hash_result = self.hasher.squeeze_n(challenge_byte_length::<T>());
+
+        #[cfg(feature = "recording")]
+        self.operations.push(TranscriptOperation::SampleChallenge);
 
         // we append this result back into the hasher to always generate a separate random value subsequently even without calling updating externally
-        self.append(&hash_result);
+        Update::update(&mut self.hasher, &hash_result);
 
-        T::from_le_bytes_mod_order(&hash_result)
+        challenge_from_hash(&hash_result)
     }
 
     // squeeze multiple number of challenges
@@ -41,6 +117,28 @@ impl<T: PrimeField> Transcript<T> {
     }
 }
 
+impl<T: PrimeField + FftField> Transcript<T> {
+    // samples a random point of the multiplicative subgroup of the given size (e.g. a FRI domain point)
+    // rather than an arbitrary field element: an index is drawn from a challenge and omega^index is returned
+    pub fn sample_subgroup_element(&mut self, subgroup_size: usize) -> T {
+        let omega = match T::get_root_of_unity(subgroup_size as u64) {
+            Some(omega) => omega,
+            None => panic!("the field has no subgroup of size {}", subgroup_size),
+        };
+
+        let index = self
+            .sample_challenge()
+            .into_bigint()
+            .as_ref()
+            .first()
+            .unwrap()
+            % subgroup_size as u64;
+
+        omega.pow([index])
+    }
+}
+
+#[derive(Clone)]
 pub struct GenericTranscript<T: PrimeField, F: GenericHashFunctionTrait> {
     _marker: PhantomData<T>,
     hash_function: F,
@@ -54,6 +152,20 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> GenericTranscript<T, F> {
         }
     }
 
+    // creates a transcript that has already absorbed a protocol tag (e.g. b"FRI")
+    pub fn with_domain(hash_function: F, tag: &[u8]) -> Self {
+        let mut transcript = Self::new(hash_function);
+        transcript.append(tag);
+
+        transcript
+    }
+
+    // Clears the hash state to start a fresh session, reusing the transcript (and its hasher) instead of building a new one
+    // This discards everything absorbed so far, including a with_domain tag, so the result matches GenericTranscript::new
+    pub fn reset(&mut self) {
+        self.hash_function.empty();
+    }
+
     pub fn append(&mut self, data: &[u8]) {
         self.hash_function.absorb(data);
     }
@@ -62,15 +174,21 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> GenericTranscript<T, F> {
         data.iter().for_each(|f| self.append(*f));
     }
 
+    // absorb a count or length as exactly 8 little-endian bytes, same as Transcript::append_u64
+    pub fn append_u64(&mut self, n: u64) {
+        self.append(&n.to_le_bytes());
+    }
+
     pub fn generate_challenge(&mut self) -> T {
         // uses the current hasher and generates a field value from it
-        let hash_result = self.hash_function.squeeze();
+        // we draw enough bytes for the field's modulus (plus a margin), so bigger fields get the entropy they need
+        let hash_result = self.hash_function.squeeze_n(challenge_byte_length::<T>());
 
         // We append this result back into the hasher
         // We do this to always generate a separate random value subsequently even without calling updating externally
         self.append(&hash_result);
 
-        T::from_le_bytes_mod_order(&hash_result)
+        challenge_from_hash(&hash_result)
     }
 
     // squeeze multiple number of challenges
@@ -91,30 +209,75 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> GenericTranscript<T, F> {
     }
 }
 
-pub trait GenericHashFunctionTrait {
+pub trait GenericHashFunctionTrait: Clone {
     fn absorb(&mut self, data: &[u8]);
     fn squeeze(&self) -> Vec<u8>;
     fn empty(&mut self);
-}
 
-impl GenericHashFunctionTrait for Keccak256 {
-    fn absorb(&mut self, data: &[u8]) {
-        sha3::Digest::update(self, data);
-    }
+    // squeeze exactly n bytes, regardless of the hasher's output size
+    // each block is absorbed back into a copy of the hasher before squeezing the next, so blocks don't repeat
+    fn squeeze_n(&self, n: usize) -> Vec<u8> {
+        let mut hasher = self.clone();
+        let mut result = hasher.squeeze();
 
-    fn squeeze(&self) -> Vec<u8> {
-        self.clone().finalize().to_vec()
-    }
+        while result.len() < n {
+            hasher.absorb(&result);
+            result.extend(hasher.squeeze());
+        }
 
-    fn empty(&mut self) {
-        self.reset();
+        result.truncate(n);
+
+        result
     }
 }
 
+// every sha3 digest plugs into the transcript the same way, only the output size differs
+macro_rules! impl_generic_hash_function_for_digest {
+    ($($digest:ty),*) => {
+        $(
+            impl GenericHashFunctionTrait for $digest {
+                fn absorb(&mut self, data: &[u8]) {
+                    sha3::Digest::update(self, data);
+                }
+
+                fn squeeze(&self) -> Vec<u8> {
+                    self.clone().finalize().to_vec()
+                }
+
+                fn empty(&mut self) {
+                    self.reset();
+                }
+            }
+        )*
+    };
+}
+
+impl_generic_hash_function_for_digest!(Keccak256, Sha3_256, Sha3_512);
+
 #[cfg(test)]
 mod test {
     use super::*;
     use ark_bn254::Fq;
+
+    // A hasher with a small output size, to check that squeezing works past a single digest
+    #[derive(Clone)]
+    struct MockHasher {
+        hasher: Keccak256,
+    }
+
+    impl GenericHashFunctionTrait for MockHasher {
+        fn absorb(&mut self, data: &[u8]) {
+            self.hasher.absorb(data);
+        }
+
+        fn squeeze(&self) -> Vec<u8> {
+            self.hasher.squeeze()[0..16].to_vec()
+        }
+
+        fn empty(&mut self) {
+            self.hasher.empty();
+        }
+    }
     use sha3::digest::core_api::CoreWrapper;
     use sha3::Keccak256Core;
 
@@ -136,6 +299,170 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_domain_tags_separate_transcripts() {
+        let mut first_transcript: Transcript<Fq> = Transcript::with_domain(b"SUMCHECK");
+        let mut second_transcript: Transcript<Fq> = Transcript::with_domain(b"GKR");
+
+        first_transcript.append(b"hello");
+        second_transcript.append(b"hello");
+
+        assert_ne!(
+            first_transcript.sample_challenge(),
+            second_transcript.sample_challenge()
+        );
+
+        let mut first_transcript: GenericTranscript<Fq, Keccak256> =
+            GenericTranscript::with_domain(Keccak256::new(), b"FRI");
+        let mut second_transcript: GenericTranscript<Fq, Keccak256> =
+            GenericTranscript::with_domain(Keccak256::new(), b"GKR");
+
+        first_transcript.append(b"hello");
+        second_transcript.append(b"hello");
+
+        assert_ne!(
+            first_transcript.generate_challenge(),
+            second_transcript.generate_challenge()
+        );
+    }
+
+    #[test]
+    fn test_append_multilinear_binds_number_of_variables() {
+        let mut first_transcript: Transcript<Fq> = Transcript::new();
+        let mut second_transcript: Transcript<Fq> = Transcript::new();
+
+        // both transcripts see the same flattened bytes: [1, 2, 3, 4, 5, 6]
+        first_transcript
+            .append_multilinear(&MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(2)]));
+        first_transcript.append_multilinear(&MultiLinearPolynomial::new(&vec![
+            Fq::from(3),
+            Fq::from(4),
+            Fq::from(5),
+            Fq::from(6),
+        ]));
+
+        second_transcript.append_multilinear(&MultiLinearPolynomial::new(&vec![
+            Fq::from(1),
+            Fq::from(2),
+            Fq::from(3),
+            Fq::from(4),
+        ]));
+        second_transcript
+            .append_multilinear(&MultiLinearPolynomial::new(&vec![Fq::from(5), Fq::from(6)]));
+
+        assert_ne!(
+            first_transcript.sample_challenge(),
+            second_transcript.sample_challenge()
+        );
+    }
+
+    #[test]
+    fn test_append_u64() {
+        let challenge_after = |n: u64| {
+            let mut transcript: Transcript<Fq> = Transcript::new();
+            transcript.append_u64(n);
+
+            transcript.sample_challenge()
+        };
+
+        assert_ne!(challenge_after(1), challenge_after(2));
+        assert_ne!(challenge_after(0), challenge_after(1 << 32));
+
+        // the encoding is exactly the 8 little-endian bytes
+        let mut transcript: Transcript<Fq> = Transcript::new();
+        transcript.append(&[1, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(challenge_after(1), transcript.sample_challenge());
+
+        let mut first_transcript: GenericTranscript<Fq, Keccak256> =
+            GenericTranscript::new(Keccak256::new());
+        let mut second_transcript: GenericTranscript<Fq, Keccak256> =
+            GenericTranscript::new(Keccak256::new());
+
+        first_transcript.append_u64(258);
+        second_transcript.append(&[2, 1, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(
+            first_transcript.generate_challenge(),
+            second_transcript.generate_challenge()
+        );
+    }
+
+    #[test]
+    fn test_sample_subgroup_element() {
+        use ark_bn254::Fr;
+        use ark_ff::{Field, One};
+
+        // bn254's base field only has a subgroup of size 2, so sample from the scalar field's larger subgroups
+        let mut transcript: Transcript<Fr> = Transcript::with_domain(b"FRI");
+
+        for subgroup_size in [1, 2, 8, 64, 1024] {
+            let element = transcript.sample_subgroup_element(subgroup_size);
+
+            assert!(element.pow([subgroup_size as u64]).is_one());
+        }
+
+        // draws keep moving through the subgroup instead of repeating the same point
+        let element = transcript.sample_subgroup_element(1 << 16);
+
+        assert_ne!(element, transcript.sample_subgroup_element(1 << 16));
+    }
+
+    #[test]
+    fn test_append_set() {
+        let items = vec![b"leaf 1".to_vec(), b"leaf 2".to_vec(), b"leaf 3".to_vec()];
+        let reordered_items = vec![items[2].clone(), items[0].clone(), items[1].clone()];
+
+        let set_challenge = |items: &[Vec<u8>]| {
+            let mut transcript: Transcript<Fq> = Transcript::new();
+            transcript.append_set(items);
+
+            transcript.sample_challenge()
+        };
+
+        let sequential_challenge = |items: &[Vec<u8>]| {
+            let mut transcript: Transcript<Fq> = Transcript::new();
+            transcript.append_n(&items.iter().map(|item| item.as_slice()).collect::<Vec<_>>());
+
+            transcript.sample_challenge()
+        };
+
+        assert_eq!(set_challenge(&items), set_challenge(&reordered_items));
+        assert_ne!(
+            sequential_challenge(&items),
+            sequential_challenge(&reordered_items)
+        );
+
+        // moving bytes between items changes the set
+        assert_ne!(
+            set_challenge(&[b"ab".to_vec(), b"c".to_vec()]),
+            set_challenge(&[b"a".to_vec(), b"bc".to_vec()])
+        );
+    }
+
+    #[test]
+    fn test_generic_transcript_reset() {
+        let mut transcript: GenericTranscript<Fq, Keccak256> =
+            GenericTranscript::with_domain(Keccak256::new(), b"FRI");
+        transcript.append(b"previous session");
+        transcript.generate_challenge();
+
+        transcript.reset();
+
+        let mut fresh_transcript: GenericTranscript<Fq, Keccak256> =
+            GenericTranscript::new(Keccak256::new());
+
+        for transcript in [&mut transcript, &mut fresh_transcript] {
+            transcript.append(b"root");
+            transcript.append_u64(16);
+        }
+
+        assert_eq!(
+            transcript.sample_n_challenges(3),
+            fresh_transcript.sample_n_challenges(3)
+        );
+    }
+
     #[test]
     fn test_generic_transcript() {
         let mut first_transcript: GenericTranscript<Fq, CoreWrapper<Keccak256Core>> =
@@ -155,4 +482,69 @@ mod test {
             second_transcript.generate_challenge()
         );
     }
+
+    #[test]
+    fn test_squeeze_n() {
+        let mut hasher = MockHasher {
+            hasher: Keccak256::new(),
+        };
+
+        hasher.absorb(b"hello");
+
+        let squeezed = hasher.squeeze_n(64);
+
+        assert_eq!(squeezed.len(), 64);
+        assert_eq!(squeezed[0..16], hasher.squeeze());
+        assert_ne!(squeezed[0..16], squeezed[16..32]);
+    }
+
+    #[test]
+    fn test_generic_transcript_uses_modulus_size_bytes() {
+        type LargeFq = ark_bls12_381::Fq;
+
+        let mut transcript: GenericTranscript<LargeFq, MockHasher> =
+            GenericTranscript::new(MockHasher {
+                hasher: Keccak256::new(),
+            });
+
+        transcript.append(b"hello");
+
+        let expected_bytes = transcript.hash_function.squeeze_n(64);
+        let challenge = transcript.generate_challenge();
+
+        // the 381 bit field needs 48 + 16 bytes, four times what a single squeeze gives
+        assert_eq!(challenge, LargeFq::from_le_bytes_mod_order(&expected_bytes));
+        assert_ne!(
+            challenge,
+            LargeFq::from_le_bytes_mod_order(&expected_bytes[0..16])
+        );
+    }
+
+    #[test]
+    fn test_challenge_bytes_scale_with_modulus_size() {
+        type LargeFq = ark_bls12_381::Fq;
+
+        assert_eq!(challenge_byte_length::<Fq>(), 32 + 16);
+        assert_eq!(challenge_byte_length::<LargeFq>(), 48 + 16);
+
+        let mut transcript: Transcript<Fq> = Transcript::new();
+        let mut large_transcript: Transcript<LargeFq> = Transcript::new();
+
+        transcript.append(b"hello");
+        large_transcript.append(b"hello");
+
+        let (expected_bytes, expected_large_bytes) = (
+            transcript.hasher.squeeze_n(48),
+            large_transcript.hasher.squeeze_n(64),
+        );
+
+        assert_eq!(
+            transcript.sample_challenge(),
+            challenge_from_hash::<Fq>(&expected_bytes)
+        );
+        assert_eq!(
+            large_transcript.sample_challenge(),
+            challenge_from_hash::<LargeFq>(&expected_large_bytes)
+        );
+    }
 }
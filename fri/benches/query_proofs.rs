@@ -0,0 +1,30 @@
+// Proof generation time with many query rounds, where collecting the openings dominates
+// Compare `cargo bench -p fri` against `cargo bench -p fri --features rayon` to see what the parallel query proofs buy
+use fri::{perform_reed_solomon, FriConfig, FriProver};
+
+use fiat_shamir::transcript::GenericTranscript;
+use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
+
+use ark_bls12_377::Fr;
+use criterion::{criterion_group, criterion_main, Criterion};
+use sha3::{Digest, Keccak256};
+
+fn bench_generate_proof(c: &mut Criterion) {
+    let coefficients = (0..1024u64).map(Fr::from).collect();
+    let blown_up_codeword = perform_reed_solomon(UnivariatePolynomial::new(coefficients), 4);
+    let config = FriConfig::new(4, 2, 512);
+
+    c.bench_function("generate_proof 4096 points 512 queries", |b| {
+        b.iter(|| {
+            FriProver::generate_proof(
+                &blown_up_codeword,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+            )
+        })
+    });
+}
+
+criterion_group!(benches, bench_generate_proof);
+criterion_main!(benches);
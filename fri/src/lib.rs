@@ -3,3 +3,12 @@ mod protocol;
 mod prover;
 mod utils;
 mod verifier;
+
+pub use protocol::FriConfig;
+pub use prover::{FriProof, FriProver, LayerIndexProof};
+pub use utils::{
+    estimate_security_bits, get_query_indexes, is_domain_generator, is_low_degree,
+    perform_reed_solomon, perform_reed_solomon_with_domain, reed_solomon_domain_size,
+    sample_query_index,
+};
+pub use verifier::FriVerifier;
@@ -1,20 +1,36 @@
 use fiat_shamir::transcript::{GenericHashFunctionTrait, GenericTranscript};
 
 use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use std::cmp::max;
 use std::marker::PhantomData;
 
 pub struct MerkleTree<T: PrimeField, F: GenericHashFunctionTrait> {
     _marker1: PhantomData<T>,
-    _marker2: PhantomData<F>,
+    // the tree only hashes with F through a transcript and never holds one, so it stays Send + Sync whatever F is
+    _marker2: PhantomData<fn() -> F>,
     hash_layers: Vec<Vec<Vec<u8>>>,
 }
 
-#[derive(Debug)]
+impl<T: PrimeField, F: GenericHashFunctionTrait> Default for MerkleTree<T, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct MerkleProof {
     hash_path: Vec<Vec<u8>>,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum MerkleProofError {
+    // The proof's hash path isn't as long as the tree is high
+    PathLengthMismatch { expected: usize, got: usize },
+    // The index isn't one of the tree's leaves; only its low bits drive the hash path, so it'd alias a real leaf
+    IndexOutOfRange { index: usize, num_leaves: usize },
+}
+
 impl MerkleProof {
     pub fn new(hash_path: Vec<Vec<u8>>) -> Self {
         Self { hash_path }
@@ -31,7 +47,7 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> MerkleTree<T, F> {
     }
 
     fn get_hash_partner(&self, hash_index: usize, layer_idx: usize) -> Vec<u8> {
-        if hash_index % 2 == 0 {
+        if hash_index.is_multiple_of(2) {
             self.hash_layers[layer_idx][hash_index + 1].to_vec()
         } else {
             self.hash_layers[layer_idx][hash_index - 1].to_vec()
@@ -47,7 +63,7 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> MerkleTree<T, F> {
         let mut layer_indexes: Vec<usize> = vec![index_to_prove];
 
         for _i in 0..indexes_length {
-            running_index = running_index / 2;
+            running_index /= 2;
             layer_indexes.push(running_index);
         }
 
@@ -60,8 +76,12 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> MerkleTree<T, F> {
         let proof_partition_indexes =
             self.get_layer_indexes_for_proof_partitions(index_to_prove, hash_path_length);
 
-        for layer_idx in 0..hash_path_length + 1 {
-            hash_path.push(self.get_hash_partner(proof_partition_indexes[layer_idx], layer_idx));
+        for (layer_idx, partition_index) in proof_partition_indexes
+            .iter()
+            .take(hash_path_length + 1)
+            .enumerate()
+        {
+            hash_path.push(self.get_hash_partner(*partition_index, layer_idx));
         }
 
         MerkleProof::new(hash_path)
@@ -110,14 +130,59 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> MerkleTree<T, F> {
         current_hashed_layer[0].to_vec()
     }
 
+    // The height of a tree over num_leaves leaves, i.e. the length of each of its proofs' hash paths
+    // Leaves are padded the same way build pads them: to a power of two, and at least 2
+    pub fn path_length(num_leaves: usize) -> usize {
+        max(num_leaves.next_power_of_two(), 2).ilog2() as usize
+    }
+
+    // num_leaves is the number of values the tree was built over, so the proof's path length can be checked against the tree's height
     pub fn verify_proof(
         &mut self,
         value: &T,
         index_of_value: usize,
         proof: &MerkleProof,
         root_hash: &[u8],
+        num_leaves: usize,
         transcript: &mut GenericTranscript<T, F>,
     ) -> bool {
+        self.try_verify_proof(
+            value,
+            index_of_value,
+            proof,
+            root_hash,
+            num_leaves,
+            transcript,
+        )
+        .unwrap_or(false)
+    }
+
+    // Same as verify_proof, but reports an out of range index or a truncated or padded hash path as an error instead of just rejecting it
+    pub fn try_verify_proof(
+        &mut self,
+        value: &T,
+        index_of_value: usize,
+        proof: &MerkleProof,
+        root_hash: &[u8],
+        num_leaves: usize,
+        transcript: &mut GenericTranscript<T, F>,
+    ) -> Result<bool, MerkleProofError> {
+        if index_of_value >= num_leaves {
+            return Err(MerkleProofError::IndexOutOfRange {
+                index: index_of_value,
+                num_leaves,
+            });
+        }
+
+        let expected_path_length = Self::path_length(num_leaves);
+
+        if proof.hash_path.len() != expected_path_length {
+            return Err(MerkleProofError::PathLengthMismatch {
+                expected: expected_path_length,
+                got: proof.hash_path.len(),
+            });
+        }
+
         let hashed_value = transcript.get_hash(&value.into_bigint().to_bytes_le());
         let proof_partition_indexes =
             self.get_layer_indexes_for_proof_partitions(index_of_value, proof.hash_path.len());
@@ -129,7 +194,7 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> MerkleTree<T, F> {
             let mut hash_1 = running_hash.to_vec();
             let mut hash_2 = hash.to_vec();
 
-            if partition_hash_idx % 2 == 0 {
+            if partition_hash_idx.is_multiple_of(2) {
                 hash_1.append(&mut hash_2);
                 running_hash = transcript.get_hash(&hash_1);
             } else {
@@ -138,7 +203,7 @@ impl<T: PrimeField, F: GenericHashFunctionTrait> MerkleTree<T, F> {
             }
         }
 
-        root_hash == &running_hash
+        Ok(root_hash == running_hash.as_slice())
     }
 }
 
@@ -183,9 +248,105 @@ mod tests {
                 4,
                 &proof_for_5,
                 &root_hash,
+                8,
                 &mut GenericTranscript::new(Keccak256::new()),
             ),
             "merkle proof not valid"
         );
     }
+
+    #[test]
+    pub fn test_verify_proof_with_wrong_path_length() {
+        let values = (1..=8).map(Fq::from).collect::<Vec<_>>();
+        let (mut merkle_tree, root_hash) = get_merkle_tree_and_root_hash(&values);
+
+        assert_eq!(
+            MerkleTree::<Fq, CoreWrapper<Keccak256Core>>::path_length(8),
+            3
+        );
+        assert_eq!(
+            MerkleTree::<Fq, CoreWrapper<Keccak256Core>>::path_length(5),
+            3
+        );
+        assert_eq!(
+            MerkleTree::<Fq, CoreWrapper<Keccak256Core>>::path_length(1),
+            1
+        );
+
+        let mut truncated_proof = merkle_tree.get_proof(4);
+        truncated_proof.hash_path.pop();
+
+        let mut padded_proof = merkle_tree.get_proof(4);
+        padded_proof.hash_path.push(root_hash.clone());
+
+        for (proof, path_length) in [(truncated_proof, 2), (padded_proof, 4)] {
+            assert_eq!(
+                merkle_tree.try_verify_proof(
+                    &Fq::from(5),
+                    4,
+                    &proof,
+                    &root_hash,
+                    8,
+                    &mut GenericTranscript::new(Keccak256::new()),
+                ),
+                Err(MerkleProofError::PathLengthMismatch {
+                    expected: 3,
+                    got: path_length
+                })
+            );
+            assert!(!merkle_tree.verify_proof(
+                &Fq::from(5),
+                4,
+                &proof,
+                &root_hash,
+                8,
+                &mut GenericTranscript::new(Keccak256::new()),
+            ));
+        }
+
+        // a genuine proof still has to be checked against the right number of leaves
+        assert!(!merkle_tree.verify_proof(
+            &Fq::from(5),
+            4,
+            &merkle_tree.get_proof(4),
+            &root_hash,
+            16,
+            &mut GenericTranscript::new(Keccak256::new()),
+        ));
+    }
+
+    #[test]
+    pub fn test_verify_proof_with_index_out_of_range() {
+        let (mut merkle_tree, root_hash) =
+            get_merkle_tree_and_root_hash(&(1..=8).map(Fq::from).collect::<Vec<_>>());
+
+        // 8 and 12 share the low 3 bits of 0 and 4, so their hash paths would otherwise verify
+        for (index, proof_index) in [(8, 0), (12, 4)] {
+            let value = Fq::from(proof_index as u64 + 1);
+            let proof = merkle_tree.get_proof(proof_index);
+
+            assert_eq!(
+                merkle_tree.try_verify_proof(
+                    &value,
+                    index,
+                    &proof,
+                    &root_hash,
+                    8,
+                    &mut GenericTranscript::new(Keccak256::new()),
+                ),
+                Err(MerkleProofError::IndexOutOfRange {
+                    index,
+                    num_leaves: 8
+                })
+            );
+            assert!(!merkle_tree.verify_proof(
+                &value,
+                index,
+                &proof,
+                &root_hash,
+                8,
+                &mut GenericTranscript::new(Keccak256::new()),
+            ));
+        }
+    }
 }
@@ -1,25 +1,163 @@
+use crate::utils::estimate_security_bits;
+
+// Parameters the prover and the verifier have to agree on for a proof to verify
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FriConfig {
+    // codeword length over number of coefficients, i.e. the inverse of the code rate (see perform_reed_solomon)
+    pub blow_up_factor: usize,
+    // number of evaluations combined into one at each layer (2 halves the layer, 4 quarters it, ...)
+    pub fold_factor: usize,
+    // number of random indexes opened through every layer
+    pub num_queries: usize,
+}
+
+impl FriConfig {
+    pub fn new(blow_up_factor: usize, fold_factor: usize, num_queries: usize) -> Self {
+        if blow_up_factor < 2 {
+            panic!("blow up factor must be at least 2");
+        }
+
+        if fold_factor < 2 {
+            panic!("fold factor must be at least 2");
+        }
+
+        if num_queries == 0 {
+            panic!("at least one query is needed");
+        }
+
+        Self {
+            blow_up_factor,
+            fold_factor,
+            num_queries,
+        }
+    }
+
+    // The domain size of every committed layer for a codeword of the given length. Folding stops once a layer has
+    // at most blow_up_factor evaluations: what's left of a low degree codeword there is a constant.
+    // None when the length isn't a power of two, or a layer that still has to be folded can't be split into cosets of fold_factor
+    pub fn domain_sizes(&self, codeword_length: usize) -> Option<Vec<usize>> {
+        if !codeword_length.is_power_of_two() {
+            return None;
+        }
+
+        let mut domain_sizes = vec![codeword_length];
+        let mut domain_size = codeword_length;
+
+        while domain_size > self.blow_up_factor {
+            if !domain_size.is_multiple_of(self.fold_factor) {
+                return None;
+            }
+
+            domain_size /= self.fold_factor;
+            domain_sizes.push(domain_size);
+        }
+
+        Some(domain_sizes)
+    }
+
+    // Conjectured soundness of proofs made with this config, see estimate_security_bits
+    pub fn security_bits(&self) -> f64 {
+        estimate_security_bits(self.blow_up_factor, self.num_queries)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::prover::FriProver;
-    use crate::utils::perform_reed_solomon;
-    use crate::verifier::FriVerifier;
+    use crate::utils::{perform_reed_solomon, perform_reed_solomon_with_domain};
+    use crate::{
+        estimate_security_bits, is_low_degree, reed_solomon_domain_size, FriConfig, FriProof,
+        FriProver, FriVerifier,
+    };
 
     use fiat_shamir::transcript::GenericTranscript;
     use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
 
     use ark_bls12_377::Fr;
-    use sha3::{Digest, Keccak256};
+    use ark_ff::{FftField, Field, UniformRand};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use sha3::{Digest, Keccak256, Sha3_256, Sha3_512};
 
     #[test]
     pub fn test_fri_protocol() {
         let init_coefficients =
             UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2)]);
         let blown_up_codeword = perform_reed_solomon(init_coefficients, 2);
+        let config = FriConfig::new(2, 2, 1);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+
+        assert!(
+            FriVerifier::verify(
+                proof,
+                &final_poly,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+                blown_up_codeword.len(),
+            ),
+            "Proof verification failed"
+        );
+    }
+
+    #[test]
+    pub fn test_fri_proof_domain_sizes() {
+        let init_coefficients =
+            UnivariatePolynomial::new((1..=8).map(Fr::from).collect::<Vec<_>>());
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 1);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+
+        // the codeword is folded in half until a layer has no more evaluations than the blow up factor
+        assert_eq!(proof.domain_sizes, vec![32, 16, 8, 4]);
+        assert_eq!(config.domain_sizes(32), Some(proof.domain_sizes.clone()));
+        assert_eq!(final_poly.coefficients.len(), 1);
+
+        assert!(
+            FriVerifier::verify(
+                proof,
+                &final_poly,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+                blown_up_codeword.len(),
+            ),
+            "Proof verification failed"
+        );
+    }
+
+    #[test]
+    pub fn test_fri_protocol_with_fold_factor_4() {
+        let init_coefficients =
+            UnivariatePolynomial::new((1..=8).map(Fr::from).collect::<Vec<_>>());
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 8);
+        let config = FriConfig::new(8, 4, 1);
+
+        assert_eq!(blown_up_codeword.len(), 64);
 
         let (final_poly, proof) = FriProver::generate_proof(
             &blown_up_codeword,
             &mut GenericTranscript::new(Keccak256::new()),
             &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+
+        // 64 -> 16 -> 4 takes 2 folds, where folding by 2 takes 3
+        assert_eq!(proof.domain_sizes, vec![64, 16, 4]);
+        assert_eq!(proof.layer_merkle_roots.len(), 3);
+        assert_eq!(
+            FriConfig::new(8, 2, 1).domain_sizes(64),
+            Some(vec![64, 32, 16, 8])
         );
 
         assert!(
@@ -28,8 +166,306 @@ mod tests {
                 &final_poly,
                 &mut GenericTranscript::new(Keccak256::new()),
                 &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+                blown_up_codeword.len(),
             ),
             "Proof verification failed"
         );
     }
+
+    #[test]
+    pub fn test_fri_proof_truncated_to_fewer_queries() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 8);
+        let generate_proof = || {
+            FriProver::generate_proof(
+                &blown_up_codeword,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+            )
+        };
+
+        let (final_poly, proof) = generate_proof();
+
+        assert_eq!(proof.num_queries(), 8);
+        assert_eq!(config.security_bits(), 16.0);
+
+        let truncated_proof = proof.with_num_queries(3);
+        let truncated_config = FriConfig::new(4, 2, 3);
+
+        assert_eq!(truncated_proof.num_queries(), 3);
+        assert_eq!(
+            truncated_config.security_bits(),
+            estimate_security_bits(4, 3)
+        );
+        assert_eq!(truncated_config.security_bits(), 6.0);
+
+        assert!(
+            FriVerifier::verify(
+                truncated_proof,
+                &final_poly,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &truncated_config,
+                blown_up_codeword.len(),
+            ),
+            "Truncated proof verification failed"
+        );
+
+        // a verifier still asking for 8 queries doesn't settle for the 3 that were sent
+        assert!(!FriVerifier::verify(
+            generate_proof().1.with_num_queries(3),
+            &final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+        ));
+    }
+
+    #[test]
+    pub fn test_fri_protocol_with_sha3_hashing() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 4);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Sha3_256::new()),
+            &mut GenericTranscript::new(Sha3_256::new()),
+            &config,
+        );
+
+        assert!(
+            FriVerifier::verify(
+                proof,
+                &final_poly,
+                &mut GenericTranscript::new(Sha3_256::new()),
+                &mut GenericTranscript::new(Sha3_256::new()),
+                &config,
+                blown_up_codeword.len(),
+            ),
+            "Proof verification failed"
+        );
+    }
+
+    #[test]
+    pub fn test_fri_protocol_with_64_byte_digests() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 4);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Sha3_512::new()),
+            &mut GenericTranscript::new(Sha3_512::new()),
+            &config,
+        );
+
+        assert!(proof
+            .layer_merkle_roots
+            .iter()
+            .all(|merkle_root| merkle_root.len() == 64));
+
+        // a proof made with one hash must not verify under another
+        assert!(!FriVerifier::verify(
+            FriProver::generate_proof(
+                &blown_up_codeword,
+                &mut GenericTranscript::new(Sha3_512::new()),
+                &mut GenericTranscript::new(Sha3_512::new()),
+                &config,
+            )
+            .1,
+            &final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+        ));
+
+        assert!(
+            FriVerifier::verify(
+                proof,
+                &final_poly,
+                &mut GenericTranscript::new(Sha3_512::new()),
+                &mut GenericTranscript::new(Sha3_512::new()),
+                &config,
+                blown_up_codeword.len(),
+            ),
+            "Proof verification failed"
+        );
+    }
+
+    #[test]
+    pub fn test_fri_protocol_with_custom_domain_generator() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+
+        // any odd power of the default 16th root of unity also generates the 16 point domain, in a different order
+        let generator = Fr::get_root_of_unity(16).unwrap().pow([3]);
+        let blown_up_codeword =
+            perform_reed_solomon_with_domain(init_coefficients.clone(), 4, generator);
+        let config = FriConfig::new(4, 2, 4);
+
+        assert_ne!(
+            blown_up_codeword,
+            perform_reed_solomon(init_coefficients, 4)
+        );
+
+        let (final_poly, proof) = FriProver::generate_proof_with_domain(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            generator,
+        );
+
+        assert!(
+            FriVerifier::verify_with_domain(
+                proof,
+                &final_poly,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+                blown_up_codeword.len(),
+                generator,
+            ),
+            "Proof verification failed"
+        );
+
+        // the verifier folding over the default domain doesn't agree with the prover's folds
+        let (final_poly, proof) = FriProver::generate_proof_with_domain(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            generator,
+        );
+
+        assert!(!FriVerifier::verify(
+            proof,
+            &final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+        ));
+    }
+
+    #[test]
+    pub fn test_is_low_degree() {
+        // degree 3 polynomial, blown up to a 16 point codeword
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+
+        assert!(is_low_degree(&blown_up_codeword, 3));
+        assert!(is_low_degree(&blown_up_codeword, 8));
+        assert!(!is_low_degree(&blown_up_codeword, 2));
+
+        // changing a single evaluation takes the codeword far from any low degree polynomial
+        let mut corrupted_codeword = blown_up_codeword.clone();
+        corrupted_codeword[5] += Fr::from(1);
+
+        assert!(!is_low_degree(&corrupted_codeword, 3));
+
+        // a random vector only interpolates to a low degree polynomial with negligible probability
+        let mut rng = rand::thread_rng();
+        let random_evals = (0..16).map(|_| Fr::rand(&mut rng)).collect::<Vec<_>>();
+
+        assert!(!is_low_degree(&random_evals, 3));
+        assert!(is_low_degree(&random_evals, 15));
+    }
+
+    #[test]
+    pub fn test_is_low_degree_rejects_non_power_of_two_length() {
+        // the first 12 evaluations of a valid 16 point codeword
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+
+        assert!(!is_low_degree(&blown_up_codeword[..12], 3));
+        assert!(!is_low_degree(&blown_up_codeword[..12], 11));
+        assert!(!is_low_degree::<Fr>(&[], 0));
+    }
+
+    #[test]
+    pub fn test_reed_solomon_domain_size() {
+        // 3 coefficients blown up 4 times would be 12 evaluations, which gets padded to 16
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2)]);
+        let domain_size = reed_solomon_domain_size(3, 4);
+
+        assert_eq!(domain_size, 16);
+        assert_eq!(
+            perform_reed_solomon(init_coefficients.clone(), 4).len(),
+            domain_size
+        );
+        assert_eq!(
+            perform_reed_solomon_with_domain(
+                init_coefficients,
+                4,
+                Fr::get_root_of_unity(16).unwrap()
+            )
+            .len(),
+            domain_size
+        );
+
+        // so the real rate is 3 / 16 rather than 1 / 4
+        assert!(3.0 / (domain_size as f64) < 1.0 / 4.0);
+
+        // power of two products are used as they are
+        assert_eq!(reed_solomon_domain_size(4, 4), 16);
+    }
+
+    #[test]
+    pub fn test_fri_proof_serialization_round_trip() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 4);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+
+        let mut serialized_proof = Vec::new();
+        proof.serialize_compressed(&mut serialized_proof).unwrap();
+
+        let deserialized_proof =
+            FriProof::<Fr>::deserialize_compressed(serialized_proof.as_slice()).unwrap();
+
+        assert_eq!(deserialized_proof.domain_sizes, proof.domain_sizes);
+        assert_eq!(
+            deserialized_proof.layer_merkle_roots,
+            proof.layer_merkle_roots
+        );
+        assert_eq!(deserialized_proof.num_queries(), proof.num_queries());
+
+        assert!(
+            FriVerifier::verify(
+                deserialized_proof,
+                &final_poly,
+                &mut GenericTranscript::new(Keccak256::new()),
+                &mut GenericTranscript::new(Keccak256::new()),
+                &config,
+                blown_up_codeword.len(),
+            ),
+            "Deserialized proof verification failed"
+        );
+
+        // a truncated encoding is rejected rather than decoded into a partial proof
+        assert!(FriProof::<Fr>::deserialize_compressed(
+            &serialized_proof[..serialized_proof.len() - 1]
+        )
+        .is_err());
+    }
 }
@@ -1,14 +1,20 @@
 use crate::merkle::{MerkleProof, MerkleTree};
-use crate::utils::{fold_layer, get_layer_proof_indexes};
+use crate::protocol::FriConfig;
+use crate::utils::{fold_layer_n, get_query_indexes, is_domain_generator, sample_query_index};
+#[cfg(debug_assertions)]
+use crate::verifier::FriVerifier;
 
-use fft::fft::FFT;
+use fft::fft::Polynomial as FFT;
 use fiat_shamir::transcript::{GenericHashFunctionTrait, GenericTranscript};
 use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
 
 use ark_ff::{FftField, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use std::marker::PhantomData;
 
-#[derive(Debug)]
+#[derive(Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct LayerIndexProof<T: FftField + PrimeField> {
     pub value: T,
     pub index: usize,
@@ -25,21 +31,47 @@ impl<T: FftField + PrimeField> LayerIndexProof<T> {
     }
 }
 
+// Serializable (CanonicalSerialize / CanonicalDeserialize) so it can be sent to a verifier over the wire
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct FriProof<T: FftField + PrimeField> {
     pub layer_merkle_roots: Vec<Vec<u8>>,
-    pub layer_proofs: Vec<Vec<LayerIndexProof<T>>>,
+    // one entry per query round, each holding the openings for every layer
+    pub layer_proofs: Vec<Vec<Vec<LayerIndexProof<T>>>>,
+    pub domain_sizes: Vec<usize>,
 }
 
 impl<T: FftField + PrimeField> FriProof<T> {
     pub fn new(
         layer_merkle_roots: Vec<Vec<u8>>,
-        layer_proofs: Vec<Vec<LayerIndexProof<T>>>,
+        layer_proofs: Vec<Vec<Vec<LayerIndexProof<T>>>>,
+        domain_sizes: Vec<usize>,
     ) -> Self {
         Self {
             layer_merkle_roots,
             layer_proofs,
+            domain_sizes,
         }
     }
+
+    pub fn num_queries(&self) -> usize {
+        self.layer_proofs.len()
+    }
+
+    // Keeps only the first k query rounds, trading soundness for a smaller proof
+    // see estimate_security_bits for what the truncated proof is still worth
+    // the verifier only accepts it with a config asking for exactly k queries
+    pub fn with_num_queries(mut self, k: usize) -> FriProof<T> {
+        if k == 0 || k > self.num_queries() {
+            panic!(
+                "cannot keep {} of the proof's {} query rounds",
+                k,
+                self.num_queries()
+            );
+        }
+
+        self.layer_proofs.truncate(k);
+        self
+    }
 }
 
 pub struct FriProver<T: FftField + PrimeField, F: GenericHashFunctionTrait> {
@@ -50,49 +82,149 @@ pub struct FriProver<T: FftField + PrimeField, F: GenericHashFunctionTrait> {
 impl<T: FftField + PrimeField, F: GenericHashFunctionTrait> FriProver<T, F> {
     fn get_layer_proofs(
         initial_index: usize,
-        merkle_trees: &Vec<MerkleTree<T, F>>,
+        merkle_trees: &[MerkleTree<T, F>],
         all_layer_evaluations: &[Vec<T>],
+        fold_factor: usize,
     ) -> Vec<Vec<LayerIndexProof<T>>> {
-        let mut given_layer_index = initial_index;
-        let mut layer_proofs: Vec<Vec<LayerIndexProof<T>>> = vec![];
-
-        for layer_idx in 0..merkle_trees.len() {
-            let (idx, negative_idx) =
-                get_layer_proof_indexes(all_layer_evaluations[layer_idx].len(), given_layer_index);
-
-            layer_proofs.push(vec![
-                LayerIndexProof::new(
-                    all_layer_evaluations[layer_idx][idx],
-                    idx,
-                    merkle_trees[layer_idx].get_proof(idx),
-                ),
-                LayerIndexProof::new(
-                    all_layer_evaluations[layer_idx][negative_idx],
-                    negative_idx,
-                    merkle_trees[layer_idx].get_proof(negative_idx),
-                ),
-            ]);
-
-            given_layer_index = idx;
-        }
+        let domain_sizes = all_layer_evaluations
+            .iter()
+            .map(|evaluations| evaluations.len())
+            .collect::<Vec<_>>();
+
+        get_query_indexes(initial_index, &domain_sizes, fold_factor)
+            .iter()
+            .enumerate()
+            .map(|(layer_idx, coset_indexes)| {
+                coset_indexes
+                    .iter()
+                    .map(|idx| {
+                        LayerIndexProof::new(
+                            all_layer_evaluations[layer_idx][*idx],
+                            *idx,
+                            merkle_trees[layer_idx].get_proof(*idx),
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Collects the openings of every query round, in the order of the initial indexes
+    // with rayon on the prover goes through get_query_proofs_parallel, and only the tests compare against this one
+    #[cfg_attr(feature = "rayon", allow(dead_code))]
+    fn get_query_proofs(
+        initial_indexes: &[usize],
+        merkle_trees: &[MerkleTree<T, F>],
+        all_layer_evaluations: &[Vec<T>],
+        fold_factor: usize,
+    ) -> Vec<Vec<Vec<LayerIndexProof<T>>>> {
+        initial_indexes
+            .iter()
+            .map(|initial_index| {
+                Self::get_layer_proofs(
+                    *initial_index,
+                    merkle_trees,
+                    all_layer_evaluations,
+                    fold_factor,
+                )
+            })
+            .collect()
+    }
 
-        layer_proofs
+    // Same as get_query_proofs, with the query rounds collected in parallel since each round's path through the layers is independent
+    // collect keeps the rounds in order, so the proof is the same as the sequential one
+    #[cfg(feature = "rayon")]
+    fn get_query_proofs_parallel(
+        initial_indexes: &[usize],
+        merkle_trees: &[MerkleTree<T, F>],
+        all_layer_evaluations: &[Vec<T>],
+        fold_factor: usize,
+    ) -> Vec<Vec<Vec<LayerIndexProof<T>>>> {
+        initial_indexes
+            .par_iter()
+            .map(|initial_index| {
+                Self::get_layer_proofs(
+                    *initial_index,
+                    merkle_trees,
+                    all_layer_evaluations,
+                    fold_factor,
+                )
+            })
+            .collect()
     }
 
+    // Every layer down to the config's blow up factor has to split into cosets of fold_factor evaluations, see FriConfig::domain_sizes
     pub fn generate_proof(
         blown_up_coded_word: &[T],
         commit_transcript: &mut GenericTranscript<T, F>,
         merkle_transcript: &mut GenericTranscript<T, F>,
+        config: &FriConfig,
+    ) -> (UnivariatePolynomial<T>, FriProof<T>) {
+        Self::generate_proof_with_domain(
+            blown_up_coded_word,
+            commit_transcript,
+            merkle_transcript,
+            config,
+            T::get_root_of_unity(blown_up_coded_word.len() as u64).unwrap(),
+        )
+    }
+
+    // Same as generate_proof, for a codeword evaluated over generator^i rather than the default subgroup
+    // (see perform_reed_solomon_with_domain). The verifier has to use the same generator.
+    pub fn generate_proof_with_domain(
+        blown_up_coded_word: &[T],
+        commit_transcript: &mut GenericTranscript<T, F>,
+        merkle_transcript: &mut GenericTranscript<T, F>,
+        config: &FriConfig,
+        generator: T,
     ) -> (UnivariatePolynomial<T>, FriProof<T>) {
         let blown_up_length = blown_up_coded_word.len();
-        let num_of_layers = (blown_up_length as i32).ilog2() as usize;
+        let FriConfig {
+            fold_factor,
+            num_queries,
+            ..
+        } = *config;
+
+        if !is_domain_generator(generator, blown_up_length) {
+            panic!(
+                "generator does not generate a domain of size {}",
+                blown_up_length
+            );
+        }
+
+        if fold_factor < 2 {
+            panic!("fold factor must be at least 2");
+        }
+
+        if num_queries == 0 {
+            panic!("at least one query is needed");
+        }
+
+        let num_of_layers = match config.domain_sizes(blown_up_length) {
+            Some(domain_sizes) => domain_sizes.len(),
+            None => panic!(
+                "a codeword of length {} can't be folded by {} down to {} evaluations",
+                blown_up_length, fold_factor, config.blow_up_factor
+            ),
+        };
+
+        // the transcripts as the verifier will start from them, for the self check at the end
+        #[cfg(debug_assertions)]
+        let (initial_commit_transcript, initial_merkle_transcript) =
+            (commit_transcript.clone(), merkle_transcript.clone());
 
         let mut layer_root_hashes: Vec<Vec<u8>> = Vec::new();
         let mut layer_evaluations = blown_up_coded_word.to_vec();
         let mut all_layer_evaluations: Vec<Vec<T>> = Vec::with_capacity(num_of_layers);
         let mut merkle_trees: Vec<MerkleTree<T, F>> = Vec::with_capacity(num_of_layers);
+        let mut layer_generator = generator;
+
+        // tag the transcript as FRI's and bind the codeword length before any layer gets committed,
+        // the verifier binds the length it expects
+        commit_transcript.append(b"FRI");
+        commit_transcript.append_u64(blown_up_length as u64);
 
-        for layer_idx in 0..num_of_layers + 1 {
+        for layer_idx in 0..num_of_layers {
             let mut merkle_tree: MerkleTree<T, F> = MerkleTree::new();
             let root_hash = merkle_tree.build(&layer_evaluations, merkle_transcript);
 
@@ -103,25 +235,316 @@ impl<T: FftField + PrimeField, F: GenericHashFunctionTrait> FriProver<T, F> {
             layer_root_hashes.push(root_hash);
             all_layer_evaluations.push(layer_evaluations.to_vec());
 
-            if layer_idx < num_of_layers {
-                layer_evaluations = fold_layer(&layer_evaluations, r);
+            if layer_idx + 1 < num_of_layers {
+                layer_evaluations =
+                    fold_layer_n(&layer_evaluations, r, fold_factor, layer_generator);
+
+                // folding maps x to x^fold_factor, so the next layer's domain is generated by generator^fold_factor
+                layer_generator = layer_generator.pow([fold_factor as u64]);
             }
         }
 
-        let initial_random_index = (*commit_transcript
-            .generate_challenge()
-            .into_bigint()
-            .as_ref()
-            .first()
-            .unwrap() as usize)
-            % blown_up_length;
+        // the indexes come out of the transcript one after the other, only collecting their openings can run in parallel
+        let initial_indexes = (0..num_queries)
+            .map(|_| sample_query_index(commit_transcript, blown_up_length))
+            .collect::<Vec<_>>();
+
+        #[cfg(not(feature = "rayon"))]
+        let layer_proofs = Self::get_query_proofs(
+            &initial_indexes,
+            &merkle_trees,
+            &all_layer_evaluations,
+            fold_factor,
+        );
+
+        #[cfg(feature = "rayon")]
+        let layer_proofs = Self::get_query_proofs_parallel(
+            &initial_indexes,
+            &merkle_trees,
+            &all_layer_evaluations,
+            fold_factor,
+        );
+
+        // store the domain size of each layer so the verifier doesn't have to re-derive it
+        let domain_sizes = all_layer_evaluations
+            .iter()
+            .map(|evaluations| evaluations.len())
+            .collect();
+
+        // the last layer of a low degree codeword is constant, so only its first coefficient can be non zero
+        let mut final_coefficients = FFT::convert_to_coefficents(&layer_evaluations);
+
+        while final_coefficients.len() > 1 && final_coefficients.last().unwrap().is_zero() {
+            final_coefficients.pop();
+        }
+
+        let final_polynomial = UnivariatePolynomial::new(final_coefficients);
+        let proof = FriProof::new(layer_root_hashes, layer_proofs, domain_sizes);
+
+        #[cfg(debug_assertions)]
+        Self::self_check(
+            &proof,
+            &final_polynomial,
+            initial_commit_transcript,
+            initial_merkle_transcript,
+            config,
+            blown_up_length,
+            generator,
+        );
+
+        (final_polynomial, proof)
+    }
+
+    // Debug builds run the verifier on every proof they generate, so a prover/verifier desync
+    // (e.g. folding over a different domain than the verifier) panics where the proof is made
+    // rather than showing up later as an unexplained verification failure
+    #[cfg(debug_assertions)]
+    fn self_check(
+        proof: &FriProof<T>,
+        final_polynomial: &UnivariatePolynomial<T>,
+        mut commit_transcript: GenericTranscript<T, F>,
+        mut merkle_transcript: GenericTranscript<T, F>,
+        config: &FriConfig,
+        codeword_length: usize,
+        generator: T,
+    ) {
+        if !FriVerifier::<T, F>::verify_degree(final_polynomial) {
+            panic!(
+                "FRI self check failed: final polynomial has {} coefficients, expected a constant",
+                final_polynomial.coefficients.len()
+            );
+        }
+
+        if !FriVerifier::verify_merkle_openings(proof, &mut merkle_transcript) {
+            panic!(
+                "FRI self check failed: an opened evaluation doesn't match its layer's Merkle root"
+            );
+        }
+
+        if !FriVerifier::verify_final_layer(
+            proof,
+            final_polynomial,
+            &mut merkle_transcript,
+            config,
+            generator,
+        ) {
+            panic!("FRI self check failed: the final polynomial doesn't match the last committed layer");
+        }
+
+        if !FriVerifier::verify_consistency(
+            proof,
+            &mut commit_transcript,
+            config,
+            codeword_length,
+            generator,
+        ) {
+            panic!("FRI self check failed: the opened layers don't fold into each other");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::perform_reed_solomon;
 
-        let layer_proofs =
-            Self::get_layer_proofs(initial_random_index, &merkle_trees, &all_layer_evaluations);
+    use ark_bls12_377::Fr;
+    use sha3::{Digest, Keccak256};
 
-        (
-            UnivariatePolynomial::new(FFT::convert_to_coefficents(&layer_evaluations)),
-            FriProof::new(layer_root_hashes, layer_proofs),
+    fn generate_test_proof() -> (UnivariatePolynomial<Fr>, FriProof<Fr>) {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+
+        // in debug builds this already runs the self check on the proof
+        FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &FriConfig::new(4, 2, 4),
         )
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    pub fn test_self_check_passes_on_valid_proof() {
+        let (final_polynomial, proof) = generate_test_proof();
+
+        FriProver::self_check(
+            &proof,
+            &final_polynomial,
+            GenericTranscript::new(Keccak256::new()),
+            GenericTranscript::new(Keccak256::new()),
+            &FriConfig::new(4, 2, 4),
+            16,
+            Fr::get_root_of_unity(16).unwrap(),
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "FRI self check failed: the opened layers don't fold into each other"
+    )]
+    pub fn test_self_check_catches_inconsistent_layers() {
+        use ark_ff::Field;
+
+        let (final_polynomial, proof) = generate_test_proof();
+
+        // checking against the wrong domain is the same desync as the prover folding over the wrong one
+        let wrong_generator = Fr::get_root_of_unity(16).unwrap().pow([3]);
+
+        FriProver::self_check(
+            &proof,
+            &final_polynomial,
+            GenericTranscript::new(Keccak256::new()),
+            GenericTranscript::new(Keccak256::new()),
+            &FriConfig::new(4, 2, 4),
+            16,
+            wrong_generator,
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "FRI self check failed: an opened evaluation doesn't match")]
+    pub fn test_self_check_catches_corrupted_layer() {
+        let (final_polynomial, mut proof) = generate_test_proof();
+
+        proof.layer_proofs[0][1][0].value += Fr::from(1);
+
+        FriProver::self_check(
+            &proof,
+            &final_polynomial,
+            GenericTranscript::new(Keccak256::new()),
+            GenericTranscript::new(Keccak256::new()),
+            &FriConfig::new(4, 2, 4),
+            16,
+            Fr::get_root_of_unity(16).unwrap(),
+        );
+    }
+
+    // Merkle trees, their roots and the evaluations of every layer
+    #[cfg(feature = "rayon")]
+    type TestLayers = (Vec<MerkleTree<Fr, Keccak256>>, Vec<Vec<u8>>, Vec<Vec<Fr>>);
+
+    // The layers of a codeword folded down to a single evaluation, with fixed folding challenges
+    #[cfg(feature = "rayon")]
+    fn get_test_layers(log_codeword_length: usize) -> TestLayers {
+        use crate::utils::fold_layer_n;
+        use ark_ff::Field;
+
+        let codeword_length = 1 << log_codeword_length;
+        let coefficients = (0..codeword_length as u64 / 4).map(Fr::from).collect();
+        let mut layer_evaluations =
+            perform_reed_solomon(UnivariatePolynomial::new(coefficients), 4);
+        let mut layer_generator = Fr::get_root_of_unity(codeword_length as u64).unwrap();
+        let (mut merkle_trees, mut merkle_roots, mut all_layer_evaluations) =
+            (vec![], vec![], vec![]);
+
+        for layer_idx in 0..log_codeword_length + 1 {
+            let mut merkle_tree = MerkleTree::new();
+            let merkle_root = merkle_tree.build(
+                &layer_evaluations,
+                &mut GenericTranscript::new(Keccak256::new()),
+            );
+
+            merkle_trees.push(merkle_tree);
+            merkle_roots.push(merkle_root);
+            all_layer_evaluations.push(layer_evaluations.clone());
+
+            if layer_idx < log_codeword_length {
+                layer_evaluations = fold_layer_n(
+                    &layer_evaluations,
+                    Fr::from(layer_idx as u64 + 3),
+                    2,
+                    layer_generator,
+                );
+                layer_generator = layer_generator.square();
+            }
+        }
+
+        (merkle_trees, merkle_roots, all_layer_evaluations)
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    pub fn test_parallel_query_proofs_match_sequential() {
+        use crate::verifier::FriVerifier;
+        use ark_serialize::CanonicalSerialize;
+
+        let (merkle_trees, merkle_roots, all_layer_evaluations) = get_test_layers(6);
+        let initial_indexes = (0..64).map(|query| (query * 37) % 64).collect::<Vec<_>>();
+
+        let proofs = [
+            FriProver::get_query_proofs(&initial_indexes, &merkle_trees, &all_layer_evaluations, 2),
+            FriProver::get_query_proofs_parallel(
+                &initial_indexes,
+                &merkle_trees,
+                &all_layer_evaluations,
+                2,
+            ),
+        ]
+        .map(|layer_proofs| {
+            FriProof::new(
+                merkle_roots.clone(),
+                layer_proofs,
+                all_layer_evaluations
+                    .iter()
+                    .map(|layer| layer.len())
+                    .collect(),
+            )
+        });
+
+        // the same openings in the same order, so both proofs verify the same way
+        let serialized_proofs = proofs.each_ref().map(|proof| {
+            let mut bytes = vec![];
+            proof.serialize_compressed(&mut bytes).unwrap();
+
+            bytes
+        });
+
+        assert_eq!(serialized_proofs[0], serialized_proofs[1]);
+
+        for proof in &proofs {
+            assert!(FriVerifier::verify_merkle_openings(
+                proof,
+                &mut GenericTranscript::new(Keccak256::new())
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    pub fn test_parallel_query_proofs_match_sequential_with_many_queries() {
+        use ark_serialize::CanonicalSerialize;
+
+        // enough queries over a large enough codeword that rayon actually splits the work
+        // (how much faster that makes the prover is measured by the generate_proof bench, not here)
+        let (merkle_trees, _, all_layer_evaluations) = get_test_layers(12);
+        let initial_indexes = (0..512)
+            .map(|query| (query * 7919) % (1 << 12))
+            .collect::<Vec<_>>();
+
+        let sequential_proofs =
+            FriProver::get_query_proofs(&initial_indexes, &merkle_trees, &all_layer_evaluations, 2);
+        let parallel_proofs = FriProver::get_query_proofs_parallel(
+            &initial_indexes,
+            &merkle_trees,
+            &all_layer_evaluations,
+            2,
+        );
+
+        assert_eq!(parallel_proofs.len(), initial_indexes.len());
+
+        // the same values, indexes and Merkle paths, in the same order
+        let serialized_proofs = [parallel_proofs, sequential_proofs].map(|layer_proofs| {
+            let mut bytes = vec![];
+            layer_proofs.serialize_compressed(&mut bytes).unwrap();
+
+            bytes
+        });
+
+        assert_eq!(serialized_proofs[0], serialized_proofs[1]);
+    }
 }
@@ -1,6 +1,7 @@
 use crate::prover::LayerIndexProof;
 
-use fft::fft::FFT;
+use fft::fft::Polynomial as FFT;
+use fiat_shamir::transcript::{GenericHashFunctionTrait, GenericTranscript};
 use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
 
 use ark_ff::{FftField, PrimeField};
@@ -9,20 +10,20 @@ pub fn compute_f_x_squared<T: FftField + PrimeField>(
     idx: usize,
     f_evaluations: (T, T),
     r: T,
-    nth_root: Option<T>,
+    nth_root: T,
 ) -> T {
     let (f_positive_x, f_negative_x) = f_evaluations;
 
     let g_of_x = (f_positive_x + f_negative_x) / T::from(2);
-    let h_of_x = (f_positive_x - f_negative_x) / (T::from(2) * nth_root.unwrap().pow([idx as u64]));
+    let h_of_x = (f_positive_x - f_negative_x) / (T::from(2) * nth_root.pow([idx as u64]));
 
     g_of_x + (r * h_of_x)
 }
 
-pub fn fold_layer<T: FftField + PrimeField>(evaluations: &[T], r: T) -> Vec<T> {
+// nth_root generates the layer's evaluation domain, i.e. evaluations[i] = f(nth_root^i)
+pub fn fold_layer<T: FftField + PrimeField>(evaluations: &[T], r: T, nth_root: T) -> Vec<T> {
     let half_length = evaluations.len() / 2;
     let mut folded_layer: Vec<T> = Vec::with_capacity(half_length);
-    let nth_root = T::get_root_of_unity(evaluations.len() as u64);
 
     for idx in 0..half_length {
         let negative_idx = idx + half_length;
@@ -38,9 +39,73 @@ pub fn fold_layer<T: FftField + PrimeField>(evaluations: &[T], r: T) -> Vec<T> {
     folded_layer
 }
 
+// Generalised fold: combines the fold_factor evaluations of a coset f(x * zeta^t), where zeta is a fold_factor-th root of unity,
+// into the folded layer's evaluation at x^fold_factor
+// f(X) = ∑ X^s * f_s(X^k) and g(Y) = ∑ r^s * f_s(Y), so
+// g(x^k) = 1/k * ∑_t f(x * zeta^t) * ∑_s (r / (x * zeta^t))^s
+pub fn compute_folded_value<T: FftField + PrimeField>(
+    idx: usize,
+    coset_evaluations: &[T],
+    r: T,
+    nth_root: T,
+    domain_size: usize,
+) -> T {
+    let fold_factor = coset_evaluations.len();
+
+    if fold_factor == 2 {
+        return compute_f_x_squared(
+            idx,
+            (coset_evaluations[0], coset_evaluations[1]),
+            r,
+            nth_root,
+        );
+    }
+
+    let coset_distance = domain_size / fold_factor;
+
+    let folded_value: T = coset_evaluations
+        .iter()
+        .enumerate()
+        .map(|(t, evaluation)| {
+            let x = nth_root.pow([(idx + t * coset_distance) as u64]);
+            let r_over_x = r * x.inverse().unwrap();
+
+            let geometric_sum: T = (0..fold_factor).map(|s| r_over_x.pow([s as u64])).sum();
+
+            *evaluation * geometric_sum
+        })
+        .sum();
+
+    folded_value / T::from(fold_factor as u64)
+}
+
+// Folds the layer by fold_factor, so each output evaluation combines fold_factor evaluations of the current layer
+pub fn fold_layer_n<T: FftField + PrimeField>(
+    evaluations: &[T],
+    r: T,
+    fold_factor: usize,
+    nth_root: T,
+) -> Vec<T> {
+    if fold_factor == 2 {
+        return fold_layer(evaluations, r, nth_root);
+    }
+
+    let coset_distance = evaluations.len() / fold_factor;
+
+    (0..coset_distance)
+        .map(|idx| {
+            let coset_evaluations = (0..fold_factor)
+                .map(|t| evaluations[idx + t * coset_distance])
+                .collect::<Vec<_>>();
+
+            compute_folded_value(idx, &coset_evaluations, r, nth_root, evaluations.len())
+        })
+        .collect()
+}
+
 pub fn get_f_squared_from_folded_layer<T: FftField + PrimeField>(
     idx: usize,
-    folded_layer: &Vec<LayerIndexProof<T>>,
+    folded_layer: &[LayerIndexProof<T>],
 ) -> T {
     folded_layer
         .iter()
@@ -49,40 +114,122 @@ pub fn get_f_squared_from_folded_layer<T: FftField + PrimeField>(
         .value
 }
 
-pub fn pad_polynomial<T: FftField + PrimeField>(
-    values: &[T],
-    final_length: usize,
-    number_to_add: T,
-) -> Vec<T> {
-    let mut final_pad = values.to_vec();
-
-    for _ in final_pad.len()..final_length {
-        final_pad.push(number_to_add);
-    }
-
-    final_pad
+// The codeword length perform_reed_solomon (and perform_reed_solomon_with_domain) actually use:
+// coefficients * blow up factor, rounded up to a power of two for the FFT and for folding.
+// When the product isn't already a power of two, the real blow up is domain size / coefficients, not the requested factor,
+// so the code rate (and estimate_security_bits) should be computed from this
+pub fn reed_solomon_domain_size(num_coefficients: usize, blow_up_factor: usize) -> usize {
+    (num_coefficients * blow_up_factor).next_power_of_two()
 }
 
 pub fn perform_reed_solomon<T: FftField + PrimeField>(
     polynomial: UnivariatePolynomial<T>,
     blow_up_factor: usize,
 ) -> Vec<T> {
-    let blown_up_length = polynomial.coefficients.len() * blow_up_factor;
-    let padded_polynomial_coefficients = pad_polynomial(
-        &polynomial.coefficients,
-        blown_up_length.next_power_of_two(),
-        T::zero(),
-    );
-
-    FFT::convert_to_evaluations(&padded_polynomial_coefficients)
+    let blown_up_length = reed_solomon_domain_size(polynomial.coefficients.len(), blow_up_factor);
+    let padded_polynomial = polynomial.pad_to(blown_up_length);
+
+    FFT::convert_to_evaluations(&padded_polynomial.coefficients)
 }
 
-pub fn get_layer_proof_indexes(n: usize, given_index: usize) -> (usize, usize) {
-    let half_length = n / 2;
+// Same as perform_reed_solomon, but evaluates over generator^0, generator^1, ... instead of the default subgroup
+// The generator must have order exactly the (power of two) codeword length for FRI to fold the codeword
+pub fn perform_reed_solomon_with_domain<T: FftField + PrimeField>(
+    polynomial: UnivariatePolynomial<T>,
+    blow_up_factor: usize,
+    generator: T,
+) -> Vec<T> {
+    let blown_up_length = reed_solomon_domain_size(polynomial.coefficients.len(), blow_up_factor);
 
-    if given_index < half_length {
-        return (given_index, given_index + half_length);
+    if !is_domain_generator(generator, blown_up_length) {
+        panic!(
+            "generator does not generate a domain of size {}",
+            blown_up_length
+        );
     }
 
-    (given_index - half_length, given_index)
+    let domain = (0..blown_up_length)
+        .map(|power| generator.pow([power as u64]))
+        .collect::<Vec<_>>();
+
+    polynomial.to_evaluations(&domain)
+}
+
+// Exact check that the evaluations (over the default subgroup of their length) come from a polynomial
+// of degree at most degree_bound: interpolate with an inverse FFT and check every higher coefficient is zero
+// Cheap enough for the prover to sanity check a codeword before committing to it
+// A codeword whose length isn't a power of two isn't one FRI can commit to, so it's rejected
+pub fn is_low_degree<T: FftField + PrimeField>(evals: &[T], degree_bound: usize) -> bool {
+    if !evals.len().is_power_of_two() {
+        return false;
+    }
+
+    FFT::convert_to_coefficents(evals)
+        .iter()
+        .skip(degree_bound + 1)
+        .all(|coefficient| coefficient.is_zero())
+}
+
+// Whether the generator has order exactly domain_size (a power of two), so its powers cover a domain FRI can fold
+pub fn is_domain_generator<T: FftField + PrimeField>(generator: T, domain_size: usize) -> bool {
+    domain_size.is_power_of_two()
+        && generator.pow([domain_size as u64]) == T::one()
+        && (domain_size == 1 || generator.pow([(domain_size / 2) as u64]) != T::one())
+}
+
+// Returns the indexes of the coset the given index belongs to, i.e. every evaluation folded together with it
+// E.g. n = 16, fold_factor = 4, given_index = 9 => [1, 5, 9, 13]
+pub fn get_layer_coset_indexes(n: usize, given_index: usize, fold_factor: usize) -> Vec<usize> {
+    let coset_distance = n / fold_factor;
+    let base_index = given_index % coset_distance;
+
+    (0..fold_factor)
+        .map(|t| base_index + t * coset_distance)
+        .collect()
+}
+
+// Returns the coset indexes opened at every layer for a query starting at initial_index
+// each coset folds into the evaluation at its first index in the next layer
+// the last layer isn't folded any further, so only the evaluation the previous coset folds into is opened there
+pub fn get_query_indexes(
+    initial_index: usize,
+    domain_sizes: &[usize],
+    fold_factor: usize,
+) -> Vec<Vec<usize>> {
+    let last_layer_idx = domain_sizes.len().saturating_sub(1);
+
+    domain_sizes
+        .iter()
+        .enumerate()
+        .scan(initial_index, |given_index, (layer_idx, domain_size)| {
+            if layer_idx == last_layer_idx {
+                return Some(vec![*given_index % domain_size]);
+            }
+
+            let coset_indexes = get_layer_coset_indexes(*domain_size, *given_index, fold_factor);
+            *given_index = coset_indexes[0];
+
+            Some(coset_indexes)
+        })
+        .collect()
+}
+
+// Samples the index a query round starts from, once every layer has been committed to the transcript
+pub fn sample_query_index<T: FftField + PrimeField, F: GenericHashFunctionTrait>(
+    commit_transcript: &mut GenericTranscript<T, F>,
+    domain_size: usize,
+) -> usize {
+    (*commit_transcript
+        .generate_challenge()
+        .into_bigint()
+        .as_ref()
+        .first()
+        .unwrap() as usize)
+        % domain_size
+}
+
+// Conjectured soundness of a FRI proof in bits: each query round catches a cheating prover
+// with probability about 1 - rho, where rho = 1 / blow_up_factor is the code rate
+pub fn estimate_security_bits(blow_up_factor: usize, num_queries: usize) -> f64 {
+    num_queries as f64 * (blow_up_factor as f64).log2()
 }
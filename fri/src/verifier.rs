@@ -1,6 +1,10 @@
 use crate::merkle::MerkleTree;
+use crate::protocol::FriConfig;
 use crate::prover::FriProof;
-use crate::utils::{compute_f_x_squared, get_f_squared_from_folded_layer};
+use crate::utils::{
+    compute_folded_value, get_f_squared_from_folded_layer, get_query_indexes, is_domain_generator,
+    sample_query_index,
+};
 
 use fiat_shamir::transcript::{GenericHashFunctionTrait, GenericTranscript};
 use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
@@ -14,74 +18,438 @@ pub struct FriVerifier<T: FftField + PrimeField, F: GenericHashFunctionTrait> {
 }
 
 impl<T: FftField + PrimeField, F: GenericHashFunctionTrait> FriVerifier<T, F> {
-    fn verify_consistency(
-        proof: FriProof<T>,
-        commit_transcript: &mut GenericTranscript<T, F>,
+    // Checks every opened evaluation's Merkle path against its layer's root
+    pub fn verify_merkle_openings(
+        proof: &FriProof<T>,
         merkle_transcript: &mut GenericTranscript<T, F>,
     ) -> bool {
-        for (layer_idx, merkle_root) in proof.layer_merkle_roots.iter().enumerate() {
-            commit_transcript.append(merkle_root);
+        let mut merkle_tree = MerkleTree::new();
+
+        if proof.domain_sizes.len() != proof.layer_merkle_roots.len() {
+            return false;
+        }
+
+        proof.layer_proofs.iter().all(|query_proofs| {
+            query_proofs.len() == proof.layer_merkle_roots.len()
+                && proof
+                    .layer_merkle_roots
+                    .iter()
+                    .zip(&proof.domain_sizes)
+                    .zip(query_proofs)
+                    .all(|((merkle_root, domain_size), layer_proofs)| {
+                        layer_proofs.iter().all(|layer_proof| {
+                            merkle_tree.verify_proof(
+                                &layer_proof.value,
+                                layer_proof.index,
+                                &layer_proof.proof,
+                                merkle_root,
+                                *domain_size,
+                                merkle_transcript,
+                            )
+                        })
+                    })
+        })
+    }
+
+    // Checks that, for every query round, each layer's opened coset folds into the evaluation opened in the next layer,
+    // and that the opened indexes are the ones the query's transcript-derived index folds through
+    // codeword_length is the length the verifier expects the committed codeword to have, never one read off the proof
+    pub fn verify_consistency(
+        proof: &FriProof<T>,
+        commit_transcript: &mut GenericTranscript<T, F>,
+        config: &FriConfig,
+        codeword_length: usize,
+        generator: T,
+    ) -> bool {
+        let fold_factor = config.fold_factor;
+        let layer_count = proof.layer_merkle_roots.len();
+
+        // the query count isn't absorbed into the transcript (a proof cut down with with_num_queries has to keep
+        // its first indexes), so checking it against the config is what stops a prover from sending fewer rounds
+        if !Self::has_expected_domain_sizes(proof, config, codeword_length)
+            || proof.domain_sizes.len() != layer_count
+            || proof.num_queries() != config.num_queries
+            || !is_domain_generator(generator, codeword_length)
+        {
+            return false;
+        }
+
+        // each layer's domain is generated by the previous layer's generator raised to the fold factor
+        let layer_generators = (0..layer_count)
+            .scan(generator, |layer_generator, _| {
+                let current_generator = *layer_generator;
+                *layer_generator = layer_generator.pow([fold_factor as u64]);
+
+                Some(current_generator)
+            })
+            .collect::<Vec<T>>();
+
+        commit_transcript.append(b"FRI");
+        commit_transcript.append_u64(codeword_length as u64);
+
+        let challenges = proof
+            .layer_merkle_roots
+            .iter()
+            .map(|merkle_root| {
+                commit_transcript.append(merkle_root);
+                commit_transcript.generate_challenge()
+            })
+            .collect::<Vec<T>>();
+
+        for query_proofs in &proof.layer_proofs {
+            if query_proofs.len() != layer_count {
+                return false;
+            }
 
-            let n = proof.layer_proofs.len();
-            let r = commit_transcript.generate_challenge();
-            let mut merkle_tree = MerkleTree::new();
-            let nth_root = T::get_root_of_unity(1 << (n - layer_idx - 1) as u64);
+            // the prover picks the same index from the transcript, so any other opening is rejected
+            let initial_index = sample_query_index(commit_transcript, codeword_length);
+            let expected_indexes =
+                get_query_indexes(initial_index, &proof.domain_sizes, fold_factor);
 
-            let evaluations_part_of_tree =
-                proof.layer_proofs[layer_idx]
+            let indexes_match =
+                query_proofs
                     .iter()
-                    .fold(true, |a: bool, b| {
-                        a || merkle_tree.verify_proof(
-                            &b.value,
-                            b.index,
-                            &b.proof,
-                            &merkle_root,
-                            merkle_transcript,
-                        )
+                    .zip(&expected_indexes)
+                    .all(|(layer_proofs, coset_indexes)| {
+                        layer_proofs
+                            .iter()
+                            .map(|layer_proof| layer_proof.index)
+                            .eq(coset_indexes.iter().copied())
                     });
 
-            if !evaluations_part_of_tree {
+            if !indexes_match {
                 return false;
             }
 
-            let positive_index = proof.layer_proofs[layer_idx][0].index;
+            for layer_idx in 0..layer_count - 1 {
+                // every layer that gets folded must open its whole coset
+                if query_proofs[layer_idx].len() != fold_factor {
+                    return false;
+                }
+
+                let positive_index = query_proofs[layer_idx][0].index;
+                let nth_root = layer_generators[layer_idx];
+
+                let coset_evaluations = query_proofs[layer_idx]
+                    .iter()
+                    .map(|layer_proof| layer_proof.value)
+                    .collect::<Vec<_>>();
 
-            if layer_idx < proof.layer_proofs.len() - 1 {
-                let f_x_squared = compute_f_x_squared(
+                let f_x_squared = compute_folded_value(
                     positive_index,
-                    (
-                        proof.layer_proofs[layer_idx][0].value,
-                        proof.layer_proofs[layer_idx][1].value,
-                    ),
-                    r,
+                    &coset_evaluations,
+                    challenges[layer_idx],
                     nth_root,
+                    proof.domain_sizes[layer_idx],
                 );
 
                 if f_x_squared
-                    != get_f_squared_from_folded_layer(
-                        positive_index,
-                        &proof.layer_proofs[layer_idx + 1],
-                    )
+                    != get_f_squared_from_folded_layer(positive_index, &query_proofs[layer_idx + 1])
                 {
                     return false;
                 }
-            };
+            }
         }
 
         true
     }
 
-    fn verify_degree(polynomial: &UnivariatePolynomial<T>) -> bool {
+    // Whether the proof's layers are the ones config folds a codeword of codeword_length into
+    pub fn has_expected_domain_sizes(
+        proof: &FriProof<T>,
+        config: &FriConfig,
+        codeword_length: usize,
+    ) -> bool {
+        config.domain_sizes(codeword_length).as_ref() == Some(&proof.domain_sizes)
+    }
+
+    // Checks that the final polynomial is a constant, which is all that is left of a low degree codeword after the last fold
+    pub fn verify_degree(polynomial: &UnivariatePolynomial<T>) -> bool {
         polynomial.coefficients.len() == 1
     }
 
+    // Checks that the final polynomial is the one the last layer was committed to: its evaluations over the last layer's domain
+    // must hash to the last Merkle root. Without this a prover could send any constant, unrelated to the folds
+    pub fn verify_final_layer(
+        proof: &FriProof<T>,
+        final_polynomial: &UnivariatePolynomial<T>,
+        merkle_transcript: &mut GenericTranscript<T, F>,
+        config: &FriConfig,
+        generator: T,
+    ) -> bool {
+        let (last_merkle_root, last_domain_size) =
+            match (proof.layer_merkle_roots.last(), proof.domain_sizes.last()) {
+                (Some(merkle_root), Some(domain_size)) => (merkle_root, *domain_size),
+                _ => return false,
+            };
+
+        let last_generator = (1..proof.domain_sizes.len()).fold(generator, |layer_generator, _| {
+            layer_generator.pow([config.fold_factor as u64])
+        });
+
+        let domain = (0..last_domain_size)
+            .map(|power| last_generator.pow([power as u64]))
+            .collect::<Vec<_>>();
+
+        let mut merkle_tree: MerkleTree<T, F> = MerkleTree::new();
+
+        merkle_tree.build(&final_polynomial.to_evaluations(&domain), merkle_transcript)
+            == *last_merkle_root
+    }
+
+    // config must match the one the prover used, and codeword_length is the length of the codeword the verifier
+    // expects to have been committed to (e.g. reed_solomon_domain_size of the claimed degree), not something taken from the proof
     pub fn verify(
         proof: FriProof<T>,
         final_polynomial: &UnivariatePolynomial<T>,
         commit_transcript: &mut GenericTranscript<T, F>,
         merkle_transcript: &mut GenericTranscript<T, F>,
+        config: &FriConfig,
+        codeword_length: usize,
+    ) -> bool {
+        let generator = match T::get_root_of_unity(codeword_length as u64) {
+            Some(generator) => generator,
+            None => return false,
+        };
+
+        Self::verify_with_domain(
+            proof,
+            final_polynomial,
+            commit_transcript,
+            merkle_transcript,
+            config,
+            codeword_length,
+            generator,
+        )
+    }
+
+    // Same as verify, for proofs over a codeword evaluated over generator^i (see FriProver::generate_proof_with_domain)
+    // Runs has_expected_domain_sizes, verify_degree, verify_merkle_openings, verify_final_layer and verify_consistency in that order;
+    // they're public so a caller can run them one by one to find out which check a rejected proof fails
+    pub fn verify_with_domain(
+        proof: FriProof<T>,
+        final_polynomial: &UnivariatePolynomial<T>,
+        commit_transcript: &mut GenericTranscript<T, F>,
+        merkle_transcript: &mut GenericTranscript<T, F>,
+        config: &FriConfig,
+        codeword_length: usize,
+        generator: T,
     ) -> bool {
-        Self::verify_degree(final_polynomial)
-            && Self::verify_consistency(proof, commit_transcript, merkle_transcript)
+        Self::has_expected_domain_sizes(&proof, config, codeword_length)
+            && Self::verify_degree(final_polynomial)
+            && proof.num_queries() == config.num_queries
+            && Self::verify_merkle_openings(&proof, merkle_transcript)
+            && Self::verify_final_layer(
+                &proof,
+                final_polynomial,
+                merkle_transcript,
+                config,
+                generator,
+            )
+            && Self::verify_consistency(
+                &proof,
+                commit_transcript,
+                config,
+                codeword_length,
+                generator,
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle::MerkleProof;
+    use crate::protocol::FriConfig;
+    use crate::prover::FriProver;
+    use crate::utils::perform_reed_solomon;
+
+    use ark_bls12_377::Fr;
+    use ark_ff::FftField;
+    use sha3::{Digest, Keccak256};
+
+    #[test]
+    pub fn test_verify_merkle_openings_with_corrupted_path() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 1);
+
+        let (_, mut proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+
+        assert!(FriVerifier::verify_merkle_openings(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new())
+        ));
+
+        // keep the opened value, but swap its authentication path for garbage
+        let path_length = blown_up_codeword.len().ilog2() as usize;
+        let digest_length = proof.layer_merkle_roots[0].len();
+        proof.layer_proofs[0][0][0].proof =
+            MerkleProof::new(vec![vec![0; digest_length]; path_length]);
+
+        assert!(!FriVerifier::verify_merkle_openings(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new())
+        ));
+
+        // the folding relation doesn't look at the paths, so it alone would still accept the proof
+        assert!(FriVerifier::verify_consistency(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+            Fr::get_root_of_unity(blown_up_codeword.len() as u64).unwrap()
+        ));
+    }
+
+    #[test]
+    pub fn test_verify_consistency_rejects_unexpected_indexes() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 8);
+
+        let (_, mut proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+        let generator = Fr::get_root_of_unity(blown_up_codeword.len() as u64).unwrap();
+
+        assert!(FriVerifier::verify_consistency(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+            generator
+        ));
+
+        // swap in another query round's openings: every index, value and path is genuine and folds correctly,
+        // but they aren't the indexes this round's challenge picks
+        let other_query = (1..proof.num_queries())
+            .find(|query| {
+                proof.layer_proofs[*query][0][0].index != proof.layer_proofs[0][0][0].index
+            })
+            .unwrap();
+        proof.layer_proofs.swap(0, other_query);
+
+        assert!(FriVerifier::verify_merkle_openings(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new())
+        ));
+        assert!(!FriVerifier::verify_consistency(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+            generator
+        ));
+    }
+
+    #[test]
+    pub fn test_verify_rejects_unrelated_final_polynomial() {
+        let init_coefficients =
+            UnivariatePolynomial::new(vec![Fr::from(5), Fr::from(3), Fr::from(2), Fr::from(7)]);
+        let blown_up_codeword = perform_reed_solomon(init_coefficients, 4);
+        let config = FriConfig::new(4, 2, 4);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+        let generator = Fr::get_root_of_unity(blown_up_codeword.len() as u64).unwrap();
+
+        assert!(FriVerifier::verify_final_layer(
+            &proof,
+            &final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            generator
+        ));
+
+        // still a constant, so the degree check alone accepts it, but it isn't what the last layer committed to
+        let unrelated_final_poly =
+            UnivariatePolynomial::new(vec![final_poly.coefficients[0] + Fr::from(1)]);
+
+        assert!(FriVerifier::<Fr, Keccak256>::verify_degree(
+            &unrelated_final_poly
+        ));
+        assert!(!FriVerifier::verify_final_layer(
+            &proof,
+            &unrelated_final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            generator
+        ));
+        assert!(!FriVerifier::verify(
+            proof,
+            &unrelated_final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            blown_up_codeword.len(),
+        ));
+    }
+
+    #[test]
+    pub fn test_verify_rejects_unexpected_codeword_length() {
+        let config = FriConfig::new(4, 2, 4);
+
+        // a degree 7 polynomial committed as a 32 point codeword, while the verifier expects degree 3, i.e. 16 points
+        let higher_degree_polynomial = UnivariatePolynomial::new((1..=8).map(Fr::from).collect());
+        let blown_up_codeword = perform_reed_solomon(higher_degree_polynomial, 4);
+
+        let (final_poly, proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+
+        assert!(!FriVerifier::<Fr, Keccak256>::has_expected_domain_sizes(
+            &proof, &config, 16
+        ));
+        assert!(!FriVerifier::verify_consistency(
+            &proof,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            16,
+            Fr::get_root_of_unity(16).unwrap()
+        ));
+        assert!(!FriVerifier::verify(
+            proof,
+            &final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            16,
+        ));
+
+        // rewriting the proof's domain sizes to the expected ones doesn't help either
+        let (final_poly, mut proof) = FriProver::generate_proof(
+            &blown_up_codeword,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+        );
+        proof.domain_sizes = config.domain_sizes(16).unwrap();
+
+        assert!(!FriVerifier::verify(
+            proof,
+            &final_poly,
+            &mut GenericTranscript::new(Keccak256::new()),
+            &mut GenericTranscript::new(Keccak256::new()),
+            &config,
+            16,
+        ));
     }
 }
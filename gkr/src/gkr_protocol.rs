@@ -1,3 +1,4 @@
+use arithmetic_circuit::circuit::Circuit;
 use kzg::multilinear::prover::MultilinearKZGProof;
 use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 use sumcheck::sumcheck_protocol::SumCheckProof;
@@ -11,6 +12,15 @@ pub struct GKRProof<T: PrimeField> {
     pub sumcheck_proofs: Vec<SumCheckProof<T>>,
 }
 
+// Rough amount of work GKRVerifier::verify_proof does for a proof, see GKRProof::verification_cost_estimate
+#[derive(Debug, PartialEq)]
+pub struct VerifyCost {
+    pub sumcheck_rounds: usize,
+    // full or partial evaluations of add_i/mul_i (or their folded versions)
+    pub gate_poly_evaluations: usize,
+    pub field_operations: usize,
+}
+
 impl<T: PrimeField> GKRProof<T> {
     pub fn new(
         output_poly: MultiLinearPolynomial<T>,
@@ -23,6 +33,84 @@ impl<T: PrimeField> GKRProof<T> {
             sumcheck_proofs,
         }
     }
+
+    // Estimates the verifier's work from the circuit's shape and the proof's round polynomials, without verifying
+    // Evaluating a polynomial is counted as ~3 field operations (a multiplication and two additions) per evaluation point,
+    // so field_operations is an upper bound on the order of magnitude rather than an exact count
+    pub fn verification_cost_estimate(&self, circuit: &Circuit<T>) -> VerifyCost {
+        let mut cost = VerifyCost {
+            sumcheck_rounds: 0,
+            gate_poly_evaluations: 0,
+            field_operations: 0,
+        };
+
+        for layer_idx in 0..circuit.get_layer_count() {
+            let (output_bits, input_bits) = circuit.gate_poly_bit_lengths(layer_idx);
+            let gate_poly_size = 1 << (output_bits + 2 * input_bits);
+            let folded_gate_poly_size = 1 << (2 * input_bits);
+
+            cost.sumcheck_rounds += 2 * input_bits;
+
+            // the output layer evaluates add_i and mul_i at a, every other layer at both r_b and r_c,
+            // then alpha-beta folds them. Either way, the results get evaluated at the sumcheck's challenges.
+            let partial_evaluations = match layer_idx {
+                0 => 2,
+                _ => 4,
+            };
+
+            cost.gate_poly_evaluations += partial_evaluations + 2;
+            cost.field_operations +=
+                3 * (partial_evaluations * gate_poly_size + 2 * folded_gate_poly_size);
+
+            if layer_idx > 0 {
+                cost.field_operations += 2 * 3 * folded_gate_poly_size;
+            }
+
+            // each round checks p(0) + p(1) against the claim and evaluates p at the round's challenge
+            cost.field_operations += self
+                .sumcheck_proofs
+                .get(layer_idx)
+                .map(|proof| {
+                    proof
+                        .round_polys
+                        .iter()
+                        .map(|round_poly| 3 * 3 * round_poly.coefficients.len())
+                        .sum()
+                })
+                .unwrap_or(0);
+
+            // the f_b_c oracle check: add_i * (w_b + w_c) + mul_i * (w_b * w_c)
+            cost.field_operations += 5;
+
+            // the input layer's w evals come from evaluating the inputs at r_b and r_c
+            if layer_idx + 1 == circuit.get_layer_count() {
+                cost.field_operations += 2 * 3 * (1 << input_bits);
+            }
+        }
+
+        cost
+    }
+}
+
+// What GKRProver::generate_proof_streaming returns once every layer's sumcheck proof has gone to the sink:
+// everything a GKRProof has but the sumcheck proofs, so it can't be handed to the verifier as a proof by mistake
+pub struct StreamedGKRProof<T: PrimeField> {
+    pub output_poly: MultiLinearPolynomial<T>,
+    pub w_polys_evals: Vec<(T, T)>,
+}
+
+impl<T: PrimeField> StreamedGKRProof<T> {
+    pub fn new(output_poly: MultiLinearPolynomial<T>, w_polys_evals: Vec<(T, T)>) -> Self {
+        Self {
+            output_poly,
+            w_polys_evals,
+        }
+    }
+
+    // Puts the streamed sumcheck proofs (in layer order) back in, giving the proof generate_proof would have returned
+    pub fn assemble(self, sumcheck_proofs: Vec<SumCheckProof<T>>) -> GKRProof<T> {
+        GKRProof::new(self.output_poly, self.w_polys_evals, sumcheck_proofs)
+    }
 }
 
 pub struct GKRProofWithKZG<T: PrimeField, P: Pairing> {
@@ -57,14 +145,21 @@ mod tests {
     use arithmetic_circuit::gate::{Gate, Operation};
     use fiat_shamir::transcript::Transcript;
     use kzg::multilinear::trusted_setup::TrustedSetup;
+    use kzg::multilinear::verifier::KzgVerifyError;
+    use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
+    use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
+    use sumcheck::verifier::SumcheckVerifier;
 
+    use crate::gkr_protocol::{GKRProof, GKRProofWithKZG};
     use crate::prover::GKRProver;
-    use crate::verifier::GKRVerifier;
+    use crate::verifier::{GKRVerifier, GKRVerifyError};
 
     use ark_bls12_381::{Bls12_381, Fr};
     use ark_bn254::Fq;
+    use ark_ff::{BigInteger, PrimeField};
+    use std::cmp::max;
 
-    pub fn get_test_circuit_and_inputs_fq() -> (Circuit<Fq>, Vec<Fq>) {
+    pub fn get_test_circuit_and_inputs<T: PrimeField>() -> (Circuit<T>, Vec<T>) {
         let circuit = Circuit::new(vec![
             vec![
                 Gate::new(0, 1, Operation::Add),
@@ -79,71 +174,815 @@ mod tests {
             vec![Gate::new(0, 1, Operation::Add)],
         ]);
 
-        let inputs = vec![
-            Fq::from(1),
-            Fq::from(2),
-            Fq::from(3),
-            Fq::from(4),
-            Fq::from(5),
-            Fq::from(6),
-            Fq::from(7),
-            Fq::from(8),
-        ];
+        let inputs = (1..=8).map(T::from).collect();
 
         (circuit, inputs)
     }
 
-    pub fn get_test_circuit_and_inputs_fr() -> (Circuit<Fr>, Vec<Fr>) {
-        let circuit = Circuit::new(vec![
+    // Balanced add/mul circuit of the given depth over 16 inputs: every layer halves the width down to 2 gates,
+    // alternating add and mul gates. Also returns the input width of each layer, starting from the input layer.
+    pub fn get_balanced_circuit_and_inputs<T: PrimeField>(
+        depth: usize,
+    ) -> (Circuit<T>, Vec<T>, Vec<usize>) {
+        let mut layer_input_widths = vec![16];
+
+        let layers = (0..depth)
+            .map(|_| {
+                let input_width = *layer_input_widths.last().unwrap();
+                let gate_count = max(input_width / 2, 2);
+
+                layer_input_widths.push(gate_count);
+
+                (0..gate_count)
+                    .map(|idx| {
+                        let operation = match idx % 2 {
+                            0 => Operation::Add,
+                            _ => Operation::Mul,
+                        };
+
+                        Gate::new(
+                            (2 * idx) % input_width,
+                            (2 * idx + 1) % input_width,
+                            operation,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        layer_input_widths.pop();
+
+        (
+            Circuit::new(layers),
+            (1..=16).map(T::from).collect(),
+            layer_input_widths,
+        )
+    }
+
+    // Runs the GKR prover and verifier over the given field, so regressions tied to a specific modulus are caught
+    fn run_gkr_over_field<T: PrimeField>() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<T>();
+
+        let gkr_proof = GKRProver::<T, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(GKRVerifier::<T, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+
+        // a wrong input must be rejected
+        let mut wrong_inputs = inputs.clone();
+        wrong_inputs[0] += T::one();
+
+        let gkr_proof = GKRProver::<T, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(!GKRVerifier::<T, Bls12_381>::verify_proof(
+            &wrong_inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_sum_check() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        // initialize both GKR prover and verifier with the same circuit
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ))
+    }
+
+    #[test]
+    pub fn test_gkr_proof_from_crate_root() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let gkr_proof: crate::GKRProof<Fq> = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        // the crate root export is the output_poly + w_polys_evals proof the prover and verifier use
+        let gkr_proof = GKRProof::new(
+            gkr_proof.output_poly,
+            gkr_proof.w_polys_evals,
+            gkr_proof.sumcheck_proofs,
+        );
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_proof_size_is_logarithmic() {
+        for depth in [2, 4, 8] {
+            let (mut circuit, inputs, layer_input_widths) =
+                get_balanced_circuit_and_inputs::<Fq>(depth);
+
+            let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+                &mut circuit,
+                &mut Transcript::new(),
+                &inputs,
+            );
+
+            // one sumcheck per layer
+            assert_eq!(gkr_proof.sumcheck_proofs.len(), circuit.get_layer_count());
+
+            // each sumcheck runs over (b, c), so its round count is twice the variable count of the layer it reads from
+            for (layer_idx, sumcheck_proof) in gkr_proof.sumcheck_proofs.iter().enumerate() {
+                let input_width = layer_input_widths[depth - layer_idx - 1];
+
+                assert_eq!(
+                    sumcheck_proof.round_polys.len(),
+                    2 * input_width.ilog2() as usize
+                );
+            }
+
+            assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+                &inputs,
+                &mut circuit,
+                &mut Transcript::new(),
+                gkr_proof
+            ));
+        }
+    }
+
+    #[test]
+    pub fn test_gkr_rejects_malformed_proofs_without_panicking() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let malformations: Vec<fn(&mut GKRProof<Fq>)> = vec![
+            |proof| {
+                proof.sumcheck_proofs.pop();
+            },
+            |proof| proof.sumcheck_proofs[1].round_polys.clear(),
+            |proof| {
+                proof.sumcheck_proofs[2].round_polys.pop();
+            },
+            |proof| proof.sumcheck_proofs[0].round_polys[0] = UnivariatePolynomial::new(vec![]),
+            |proof| {
+                proof.w_polys_evals.pop();
+            },
+            |proof| proof.w_polys_evals.push((Fq::from(1), Fq::from(2))),
+        ];
+
+        for malform in malformations {
+            let mut gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+                &mut circuit,
+                &mut Transcript::new(),
+                &inputs,
+            );
+
+            malform(&mut gkr_proof);
+
+            assert!(!GKRVerifier::<Fq, Bls12_381>::verify_proof(
+                &inputs,
+                &mut circuit,
+                &mut Transcript::new(),
+                gkr_proof
+            ));
+        }
+
+        // inputs that don't match the circuit's input layer
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(!GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs[..4],
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_with_kzg_rejects_malformed_proofs_without_panicking() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fr>();
+
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        let malformations: Vec<fn(&mut GKRProofWithKZG<Fr, Bls12_381>)> = vec![
+            |proof| {
+                proof.kzg_proofs.pop();
+            },
+            |proof| {
+                proof.sumcheck_proofs.pop();
+            },
+            |proof| proof.sumcheck_proofs[2].round_polys.clear(),
+            |proof| proof.kzg_proofs[0].q_taus.clear(),
+        ];
+
+        for malform in malformations {
+            let mut gkr_proof_with_kzg = GKRProver::<Fr, Bls12_381>::generate_proof_with_kzg(
+                &mut circuit,
+                &mut Transcript::new(),
+                &inputs,
+                &trusted_setup.encrypted_lagrange_basis,
+            );
+
+            malform(&mut gkr_proof_with_kzg);
+
+            assert!(!GKRVerifier::verify_proof_with_kzg(
+                &mut circuit,
+                &mut Transcript::new(),
+                gkr_proof_with_kzg,
+                &trusted_setup.encrypted_taus
+            ));
+        }
+    }
+
+    #[test]
+    pub fn test_gkr_rejects_w_eval_changed_after_challenges() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        // the prover commits each layer's w evals before sampling alpha and beta from them,
+        // so changing an eval after the fact changes the challenges the verifier derives
+        for layer_idx in 0..circuit.get_layer_count() - 1 {
+            let mut gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+                &mut circuit,
+                &mut Transcript::new(),
+                &inputs,
+            );
+
+            gkr_proof.w_polys_evals[layer_idx].0 += Fq::from(1);
+
+            assert!(!GKRVerifier::<Fq, Bls12_381>::verify_proof(
+                &inputs,
+                &mut circuit,
+                &mut Transcript::new(),
+                gkr_proof
+            ));
+        }
+    }
+
+    #[test]
+    pub fn test_gkr_over_tiled_circuit() {
+        let mut sub_circuit: Circuit<Fq> =
+            Circuit::new(vec![vec![Gate::new(0, 1, Operation::Add)]]);
+        let mut circuit = Circuit::tile(&sub_circuit, 4);
+        let inputs = (1..=8).map(Fq::from).collect::<Vec<_>>();
+
+        // each copy's output is the sub circuit evaluated on its own pair of inputs
+        let tiled_outputs = circuit.evaluate_at_input(inputs.clone()).pop().unwrap();
+
+        inputs
+            .chunks(2)
+            .enumerate()
+            .for_each(|(copy_idx, copy_inputs)| {
+                let sub_circuit_output = sub_circuit
+                    .evaluate_at_input(copy_inputs.to_vec())
+                    .pop()
+                    .unwrap();
+
+                assert_eq!(
+                    tiled_outputs.get_evaluation_points()[copy_idx],
+                    sub_circuit_output.get_evaluation_points()[0]
+                );
+            });
+
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_verification_cost_estimate() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        let cost = gkr_proof.verification_cost_estimate(&circuit);
+
+        // b and c take 1, 2 and 3 bits from the output layer down, and every variable of them is a sumcheck round
+        let per_layer_variables = (0..circuit.get_layer_count())
+            .map(|layer_idx| 2 * circuit.gate_poly_bit_lengths(layer_idx).1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(per_layer_variables, vec![2, 4, 6]);
+        assert_eq!(
+            cost.sumcheck_rounds,
+            per_layer_variables.iter().sum::<usize>()
+        );
+        assert_eq!(
+            cost.sumcheck_rounds,
+            gkr_proof
+                .sumcheck_proofs
+                .iter()
+                .map(|proof| proof.round_polys.len())
+                .sum::<usize>()
+        );
+
+        // 4 evaluations for the output layer, 6 for each of the other two
+        assert_eq!(cost.gate_poly_evaluations, 16);
+        assert!(cost.field_operations > 0);
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_streaming_proof_matches_batch_proof() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let batch_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        let mut streamed_proofs = vec![];
+        let streamed_proof = GKRProver::<Fq, Bls12_381>::generate_proof_streaming(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+            |layer_idx, sumcheck_proof| {
+                // layers come in order, one proof each
+                assert_eq!(layer_idx, streamed_proofs.len());
+                streamed_proofs.push(sumcheck_proof);
+            },
+        );
+
+        assert_eq!(streamed_proofs.len(), circuit.get_layer_count());
+
+        let streamed_proof = streamed_proof.assemble(streamed_proofs);
+
+        assert_eq!(streamed_proof.output_poly, batch_proof.output_poly);
+        assert_eq!(streamed_proof.w_polys_evals, batch_proof.w_polys_evals);
+        assert_eq!(streamed_proof.sumcheck_proofs, batch_proof.sumcheck_proofs);
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            streamed_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_layer_by_layer_verification() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        let mut transcript = Transcript::new();
+        let mut state = GKRVerifier::<Fq, Bls12_381>::begin_verification(
+            &circuit,
+            &mut transcript,
+            &gkr_proof.output_poly,
+        )
+        .unwrap();
+
+        for (layer_idx, sumcheck_proof) in gkr_proof.sumcheck_proofs.iter().enumerate() {
+            assert!(!state.is_complete(&circuit));
+
+            state = GKRVerifier::<Fq, Bls12_381>::verify_layer(
+                state,
+                &circuit,
+                layer_idx,
+                sumcheck_proof,
+                gkr_proof.w_polys_evals.get(layer_idx).copied(),
+                &inputs,
+            )
+            .unwrap();
+
+            assert_eq!(state.layers_verified(), layer_idx + 1);
+        }
+
+        assert!(state.is_complete(&circuit));
+
+        // the layer by layer verifier leaves the transcript where the monolithic one does
+        let mut monolithic_transcript = Transcript::new();
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut monolithic_transcript,
+            gkr_proof
+        ));
+        assert_eq!(
+            transcript.sample_challenge(),
+            monolithic_transcript.sample_challenge()
+        );
+    }
+
+    #[test]
+    pub fn test_gkr_layer_by_layer_verification_rejects_bad_layers() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let mut gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        let mut transcript = Transcript::new();
+        let state = GKRVerifier::<Fq, Bls12_381>::begin_verification(
+            &circuit,
+            &mut transcript,
+            &gkr_proof.output_poly,
+        )
+        .unwrap();
+
+        // layers can't be skipped
+        assert!(matches!(
+            GKRVerifier::<Fq, Bls12_381>::verify_layer(
+                state,
+                &circuit,
+                1,
+                &gkr_proof.sumcheck_proofs[1],
+                gkr_proof.w_polys_evals.get(1).copied(),
+                &inputs,
+            ),
+            Err(GKRVerifyError::MalformedProof)
+        ));
+
+        gkr_proof.w_polys_evals[0].0 += Fq::from(1);
+
+        let mut transcript = Transcript::new();
+        let state = GKRVerifier::<Fq, Bls12_381>::begin_verification(
+            &circuit,
+            &mut transcript,
+            &gkr_proof.output_poly,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            GKRVerifier::<Fq, Bls12_381>::verify_layer(
+                state,
+                &circuit,
+                0,
+                &gkr_proof.sumcheck_proofs[0],
+                gkr_proof.w_polys_evals.first().copied(),
+                &inputs,
+            ),
+            Err(GKRVerifyError::LayerCheckFailed(0))
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_verify_proof_with_inputs() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert_eq!(
+            GKRVerifier::<Fq, Bls12_381>::verify_proof_with_inputs(
+                &inputs,
+                &mut circuit,
+                &mut Transcript::new(),
+                gkr_proof
+            ),
+            Ok(())
+        );
+
+        // claim a different output
+        let mut gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+        let mut wrong_output = gkr_proof.output_poly.get_evaluation_points().clone();
+        wrong_output[0] += Fq::from(1);
+        gkr_proof.output_poly = MultiLinearPolynomial::new(&wrong_output);
+
+        let mut transcript = Transcript::new();
+
+        assert_eq!(
+            GKRVerifier::<Fq, Bls12_381>::verify_proof_with_inputs(
+                &inputs,
+                &mut circuit,
+                &mut transcript,
+                gkr_proof
+            ),
+            Err(GKRVerifyError::OutputMismatch)
+        );
+
+        // it was rejected before anything went into the transcript, i.e. before any sumcheck work
+        assert_eq!(
+            transcript.sample_challenge(),
+            Transcript::<Fq>::new().sample_challenge()
+        );
+    }
+
+    #[test]
+    pub fn test_gkr_over_balanced_circuit() {
+        let mut circuit: Circuit<Fq> = Circuit::balanced(&[Operation::Add], 8);
+        let inputs = (1..=8).map(Fq::from).collect::<Vec<_>>();
+
+        let output = circuit.evaluate_at_input(inputs.clone()).pop().unwrap();
+
+        assert_eq!(
+            output.get_evaluation_points()[0],
+            inputs.iter().copied().sum::<Fq>()
+        );
+
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_with_non_power_of_two_inputs() {
+        let mut circuit = Circuit::new(vec![
             vec![
                 Gate::new(0, 1, Operation::Add),
-                Gate::new(2, 3, Operation::Add),
-                Gate::new(4, 5, Operation::Add),
-                Gate::new(6, 7, Operation::Mul),
+                Gate::new(1, 2, Operation::Mul),
             ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ]);
+        let inputs = vec![Fq::from(2), Fq::from(3), Fq::from(4)];
+
+        // both sides pad the 3 inputs to 4, so the proof goes through
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+
+        // 5 inputs pad to 8, which doesn't match the circuit's input layer
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        assert!(!GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &[inputs.clone(), vec![Fq::from(5), Fq::from(6)]].concat(),
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_rejects_wrong_output_width() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let mut gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+        );
+
+        gkr_proof.output_poly = MultiLinearPolynomial::new(&vec![Fq::from(0); 8]);
+
+        assert!(!GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ))
+    }
+
+    fn get_fan_in_3_circuit_and_inputs() -> (Circuit<Fq>, Vec<Fq>) {
+        let circuit = Circuit::new(vec![
             vec![
-                Gate::new(0, 1, Operation::Mul),
-                Gate::new(2, 3, Operation::Add),
+                Gate::with_inputs(vec![0, 1, 2], Operation::Add),
+                Gate::with_inputs(vec![3, 4, 5], Operation::Mul),
             ],
             vec![Gate::new(0, 1, Operation::Add)],
         ]);
 
-        let inputs = vec![
-            Fr::from(1),
-            Fr::from(2),
-            Fr::from(3),
-            Fr::from(4),
-            Fr::from(5),
-            Fr::from(6),
-            Fr::from(7),
-            Fr::from(8),
-        ];
+        (circuit, (1..=6).map(Fq::from).collect())
+    }
 
-        (circuit, inputs)
+    #[test]
+    #[should_panic(expected = "GKR supports only fan-in 2 layers, layer 1 has fan-in 3")]
+    pub fn test_gkr_prover_rejects_fan_in_3() {
+        let (mut circuit, inputs) = get_fan_in_3_circuit_and_inputs();
+
+        GKRProver::<Fq, Bls12_381>::generate_proof(&mut circuit, &mut Transcript::new(), &inputs);
     }
 
     #[test]
-    pub fn test_gkr_sum_check() {
-        let (mut circuit, inputs) = get_test_circuit_and_inputs_fq();
+    pub fn test_gkr_verifier_rejects_fan_in_3() {
+        let (mut circuit, inputs) = get_fan_in_3_circuit_and_inputs();
 
-        // initialize both GKR prover and verifier with the same circuit
+        // a genuine proof for a fan-in 2 circuit of the same shape, so only the fan-in is wrong
+        let mut fan_in_2_circuit = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::new(2, 3, Operation::Mul),
+            ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ]);
         let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut fan_in_2_circuit,
+            &mut Transcript::new(),
+            &(1..=4).map(Fq::from).collect::<Vec<_>>(),
+        );
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::begin_verification(
+            &circuit,
+            &mut Transcript::new(),
+            &gkr_proof.output_poly,
+        )
+        .is_err_and(|err| err == GKRVerifyError::UnsupportedFanIn(1, 3)));
+        assert!(!GKRVerifier::<Fq, Bls12_381>::verify_proof(
+            &inputs,
+            &mut circuit,
+            &mut Transcript::new(),
+            gkr_proof
+        ));
+    }
+
+    #[test]
+    pub fn test_gkr_verifier_rejects_mixed_fan_in() {
+        // layer_fan_in panics on a layer like this, the verifier has to report it instead
+        let circuit: Circuit<Fq> = Circuit::new(vec![
+            vec![
+                Gate::new(0, 1, Operation::Add),
+                Gate::with_inputs(vec![2, 3, 4], Operation::Mul),
+            ],
+            vec![Gate::new(0, 1, Operation::Add)],
+        ]);
+        let output_poly = MultiLinearPolynomial::new(&vec![Fq::from(0); circuit.output_width()]);
+
+        assert!(GKRVerifier::<Fq, Bls12_381>::begin_verification(
+            &circuit,
+            &mut Transcript::new(),
+            &output_poly,
+        )
+        .is_err_and(|err| err == GKRVerifyError::UnsupportedFanIn(1, 3)));
+    }
+
+    #[test]
+    pub fn test_gkr_proof_challenge_trace() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let (gkr_proof, challenge_trace) = GKRProver::<Fq, Bls12_381>::generate_proof_with_trace(
             &mut circuit,
             &mut Transcript::new(),
             &inputs,
         );
 
+        assert_eq!(challenge_trace.len(), circuit.get_layer_count());
+
+        // replay the verifier's transcript steps and check it derives the same challenges at each layer
+        // the entry points tag the transcript they're given before absorbing anything else
+        let mut transcript = Transcript::with_domain(b"GKR");
+
+        transcript.append_u64(circuit.get_layer_count() as u64);
+        transcript.append_multilinear(&gkr_proof.output_poly);
+        transcript.sample_n_challenges(gkr_proof.output_poly.number_of_variables() as usize);
+
+        for (layer_idx, (sumcheck_proof, layer_challenges)) in gkr_proof
+            .sumcheck_proofs
+            .iter()
+            .zip(&challenge_trace)
+            .enumerate()
+        {
+            if layer_idx > 0 {
+                let (w_i_b_eval, w_i_c_eval) = gkr_proof.w_polys_evals[layer_idx - 1];
+
+                transcript.append_n(&[
+                    &w_i_b_eval.into_bigint().to_bytes_le(),
+                    &w_i_c_eval.into_bigint().to_bytes_le(),
+                ]);
+                transcript.sample_n_challenges(2);
+            }
+
+            let (is_verified, _, challenges) = SumcheckVerifier::partial_verify(
+                sumcheck_proof,
+                &mut transcript,
+                layer_challenges.len(),
+            );
+
+            assert!(is_verified);
+            assert_eq!(
+                &challenges
+                    .iter()
+                    .map(|challenge| challenge.unwrap())
+                    .collect::<Vec<_>>(),
+                layer_challenges
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "recording")]
+    pub fn test_gkr_verifier_replays_prover_transcript() {
+        use fiat_shamir::transcript::TranscriptOperation;
+
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fq>();
+
+        let mut prover_transcript = Transcript::new();
+        let mut verifier_transcript = Transcript::new();
+
+        let gkr_proof = GKRProver::<Fq, Bls12_381>::generate_proof(
+            &mut circuit,
+            &mut prover_transcript,
+            &inputs,
+        );
+
         assert!(GKRVerifier::<Fq, Bls12_381>::verify_proof(
             &inputs,
             &mut circuit,
-            &mut Transcript::new(),
+            &mut verifier_transcript,
             gkr_proof
-        ))
+        ));
+
+        // both sides tag the transcript as GKR's before absorbing anything else
+        for transcript in [&prover_transcript, &verifier_transcript] {
+            assert_eq!(
+                transcript.operations()[0],
+                TranscriptOperation::Append(b"GKR".to_vec())
+            );
+        }
+
+        // the verifier must issue the exact same appends and samples, in the same order
+        for (idx, (prover_operation, verifier_operation)) in prover_transcript
+            .operations()
+            .iter()
+            .zip(verifier_transcript.operations())
+            .enumerate()
+        {
+            assert_eq!(
+                prover_operation, verifier_operation,
+                "transcripts drifted at operation {}",
+                idx
+            );
+        }
+
+        assert_eq!(
+            prover_transcript.operations().len(),
+            verifier_transcript.operations().len()
+        );
     }
 
     #[test]
     pub fn test_gkr_sumcheck_with_kzg() {
-        let (mut circuit, inputs) = get_test_circuit_and_inputs_fr();
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fr>();
 
         let trusted_setup: TrustedSetup<Fr, Bls12_381> =
             TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
@@ -163,4 +1002,50 @@ mod tests {
             &trusted_setup.encrypted_taus
         ))
     }
+
+    #[test]
+    pub fn test_gkr_sumcheck_with_kzg_reports_failed_opening() {
+        let (mut circuit, inputs) = get_test_circuit_and_inputs::<Fr>();
+
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        let mut gkr_proof_with_kzg = GKRProver::<Fr, Bls12_381>::generate_proof_with_kzg(
+            &mut circuit,
+            &mut Transcript::new(),
+            &inputs,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        // corrupt the claimed evaluation of the input poly at r_c
+        gkr_proof_with_kzg.kzg_proofs[1].v += Fr::from(1);
+
+        assert_eq!(
+            GKRVerifier::verify_proof_with_kzg_detailed(
+                &mut circuit,
+                &mut Transcript::new(),
+                gkr_proof_with_kzg,
+                &trusted_setup.encrypted_taus
+            ),
+            Err(GKRVerifyError::InputOpeningFailed(
+                1,
+                KzgVerifyError::PairingCheckFailed
+            ))
+        )
+    }
+
+    #[test]
+    pub fn test_gkr_over_bn254_fr() {
+        run_gkr_over_field::<ark_bn254::Fr>();
+    }
+
+    #[test]
+    pub fn test_gkr_over_bls12_381_fr() {
+        run_gkr_over_field::<ark_bls12_381::Fr>();
+    }
+
+    #[test]
+    pub fn test_gkr_over_bls12_377_fr() {
+        run_gkr_over_field::<ark_bls12_377::Fr>();
+    }
 }
@@ -2,3 +2,5 @@ pub mod gkr_protocol;
 pub mod prover;
 pub mod utils;
 pub mod verifier;
+
+pub use gkr_protocol::{GKRProof, StreamedGKRProof};
@@ -4,9 +4,12 @@ use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 use polynomials::product_polynomial::ProductPolynomial;
 use polynomials::sum_polynomial::SumPolynomial;
 use sumcheck::prover::SumcheckProver;
+use sumcheck::sumcheck_protocol::SumCheckProof;
 
-use crate::gkr_protocol::{GKRProof, GKRProofWithKZG};
-use crate::utils::{get_evaluated_muli_addi_at_a, get_folded_claim_sum, get_folded_polys};
+use crate::gkr_protocol::{GKRProof, GKRProofWithKZG, StreamedGKRProof};
+use crate::utils::{
+    find_unsupported_fan_in, get_evaluated_muli_addi_at_a, get_folded_claim_sum, get_folded_polys,
+};
 
 use ark_ec::pairing::Pairing;
 use ark_ff::{BigInteger, PrimeField};
@@ -21,20 +24,84 @@ pub struct GKRProver<T: PrimeField, P: Pairing> {
 }
 
 impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
+    // Circuits with wider gates can be built and evaluated, but GKR can't prove them (see find_unsupported_fan_in)
+    fn check_fan_in(circuit: &Circuit<T>) {
+        if let Some((layer_idx, fan_in)) = find_unsupported_fan_in(circuit) {
+            panic!(
+                "GKR supports only fan-in 2 layers, layer {} has fan-in {}",
+                layer_idx, fan_in
+            );
+        }
+    }
+
     pub fn generate_proof(
         circuit: &mut Circuit<T>,
         transcript: &mut Transcript<T>,
         inputs: &[T],
     ) -> GKRProof<T> {
-        // Evaluate the polynomials at the inputs to be able to get w_polys on each layer
-        let circuit_evaluations = circuit.evaluate_at_input(Vec::from(inputs));
+        let (proof, _) = Self::generate_proof_with_trace(circuit, transcript, inputs);
 
-        // Initialize vecs to store the w_poly_evaluations and the sumcheck proofs at each step
-        let (mut w_polys_evals, mut sum_check_proofs) = (
+        proof
+    }
+
+    // Same as generate_proof, but also returns the sumcheck challenges sampled at each layer.
+    // This is useful for debugging transcript mismatches between the prover and the verifier.
+    pub fn generate_proof_with_trace(
+        circuit: &mut Circuit<T>,
+        transcript: &mut Transcript<T>,
+        inputs: &[T],
+    ) -> (GKRProof<T>, Vec<Vec<T>>) {
+        let (mut sum_check_proofs, mut challenge_trace) = (
             Vec::with_capacity(circuit.get_layer_count()),
             Vec::with_capacity(circuit.get_layer_count()),
         );
 
+        let streamed_proof = Self::prove_layers(
+            circuit,
+            transcript,
+            inputs,
+            |_, sumcheck_proof, random_points| {
+                sum_check_proofs.push(sumcheck_proof);
+                challenge_trace.push(random_points);
+            },
+        );
+
+        (streamed_proof.assemble(sum_check_proofs), challenge_trace)
+    }
+
+    // Same as generate_proof, but hands each layer's sumcheck proof to the sink (with the layer index) as soon as it's made,
+    // instead of holding them all until the end. What's returned has everything else:
+    // assembling it with the streamed proofs in layer order gives generate_proof's output.
+    pub fn generate_proof_streaming(
+        circuit: &mut Circuit<T>,
+        transcript: &mut Transcript<T>,
+        inputs: &[T],
+        mut sink: impl FnMut(usize, SumCheckProof<T>),
+    ) -> StreamedGKRProof<T> {
+        Self::prove_layers(
+            circuit,
+            transcript,
+            inputs,
+            |layer_idx, sumcheck_proof, _| sink(layer_idx, sumcheck_proof),
+        )
+    }
+
+    // Runs the GKR prover, passing each layer's sumcheck proof and challenges to on_layer as they're produced
+    // What's returned holds the output poly and the w evals, the sumcheck proofs only go to on_layer
+    fn prove_layers(
+        circuit: &mut Circuit<T>,
+        transcript: &mut Transcript<T>,
+        inputs: &[T],
+        mut on_layer: impl FnMut(usize, SumCheckProof<T>, Vec<T>),
+    ) -> StreamedGKRProof<T> {
+        Self::check_fan_in(circuit);
+
+        // Evaluate the polynomials at the inputs to be able to get w_polys on each layer
+        let circuit_evaluations = circuit.evaluate_at_input(Vec::from(inputs));
+
+        // Initialize a vec to store the w_poly_evaluations at each step
+        let mut w_polys_evals = Vec::with_capacity(circuit.get_layer_count());
+
         let length_of_rs = circuit
             .get_w_i(0, &circuit_evaluations)
             .number_of_variables();
@@ -42,8 +109,10 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
         // This variable stores the w_poly for each layer
         let mut running_layer_polynomial = circuit.get_w_i(0, &circuit_evaluations);
 
-        // Commit to the output layer poly by appending to the transcript
-        transcript.append(&running_layer_polynomial.to_bytes());
+        // Tag the transcript as GKR's, bind the circuit's depth, then commit to the output layer poly by appending to the transcript
+        transcript.append(b"GKR");
+        transcript.append_u64(circuit.get_layer_count() as u64);
+        transcript.append_multilinear(&running_layer_polynomial);
 
         // generate a number of rs for to evaluate the output layer depending on the number of outputs of the circuit.
         let mut random_values: Vec<Option<T>> = transcript
@@ -52,15 +121,14 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
             .map(|challenge| Some(challenge))
             .collect();
 
-        for layer_idx in 0..circuit.get_layer_count() {
+        for (layer_idx, (addi_a_b_c, muli_a_b_c)) in
+            circuit.all_gate_polys().into_iter().enumerate()
+        {
             // Prover is sending the verifier the following at each step:
             //   - W_output poly of the first layer, t
             //   - The evaluations of W_poly of the subsequent layers -> Perform alpha beta folding if more than one output to form one output poly
             //   - Intermediate Sumcheck proof
 
-            let (muli_a_b_c, addi_a_b_c) =
-                (circuit.get_mul_i(layer_idx), circuit.get_add_i(layer_idx));
-
             let (claim_sum, new_muli_b_c, new_addi_b_c) = match layer_idx {
                 0 => {
                     let (muli_b_c, addi_b_c) =
@@ -143,14 +211,10 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
             random_values = random_points.iter().map(|point| Some(*point)).collect();
             running_layer_polynomial = next_w_i;
 
-            sum_check_proofs.push(sumcheck_proof);
+            on_layer(layer_idx, sumcheck_proof, random_points);
         }
 
-        GKRProof::new(
-            circuit.get_w_i(0, &circuit_evaluations),
-            w_polys_evals,
-            sum_check_proofs,
-        )
+        StreamedGKRProof::new(circuit.get_w_i(0, &circuit_evaluations), w_polys_evals)
     }
 
     // TODO: Add doc comments for logic
@@ -160,6 +224,8 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
         inputs: &[T],
         encrypted_lagrange_basis: &[P::G1],
     ) -> GKRProofWithKZG<T, P> {
+        Self::check_fan_in(circuit);
+
         // Evaluate the polynomials at the inputs to be able to get w_polys on each layer
         let circuit_evaluations = circuit.evaluate_at_input(Vec::from(inputs));
 
@@ -177,15 +243,15 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
         let mut running_layer_polynomial = circuit.get_w_i(0, &circuit_evaluations);
 
         let input_commitment = MultilinearKZGProver::<T, P>::generate_commitment(
-            &MultiLinearPolynomial::new(&inputs.to_vec()),
+            &MultiLinearPolynomial::new(&Circuit::pad_inputs(inputs)),
             encrypted_lagrange_basis,
         );
 
-        // Commit to the input (f_tau) and output layer poly by appending to the transcript
-        transcript.append_n(&[
-            &input_commitment.to_string().as_bytes(),
-            &running_layer_polynomial.to_bytes(),
-        ]);
+        // Tag the transcript as GKR's, then commit to the input (f_tau), the circuit's depth and output layer poly by appending to the transcript
+        transcript.append(b"GKR");
+        transcript.append(input_commitment.to_string().as_bytes());
+        transcript.append_u64(circuit.get_layer_count() as u64);
+        transcript.append_multilinear(&running_layer_polynomial);
 
         // generate a number of rs for to evaluate the output layer depending on the number of outputs of the circuit.
         let mut random_values: Vec<Option<T>> = transcript
@@ -196,15 +262,14 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
 
         let mut openings = vec![T::zero(); length_of_rs as usize];
 
-        for layer_idx in 0..circuit.get_layer_count() {
+        for (layer_idx, (addi_a_b_c, muli_a_b_c)) in
+            circuit.all_gate_polys().into_iter().enumerate()
+        {
             // Prover is sending the verifier the following at each step:
             //   - W_output poly of the first layer, t
             //   - The evaluations of W_poly of the subsequent layers -> Perform alpha beta folding if more than one output to form one output poly
             //   - Intermediate Sumcheck proof
 
-            let (muli_a_b_c, addi_a_b_c) =
-                (circuit.get_mul_i(layer_idx), circuit.get_add_i(layer_idx));
-
             let (claim_sum, new_muli_b_c, new_addi_b_c) = match layer_idx {
                 0 => {
                     let (muli_b_c, addi_b_c) =
@@ -293,10 +358,11 @@ impl<T: PrimeField, P: Pairing> GKRProver<T, P> {
             .to_vec()
             .iter()
             .map(|opening| {
-                MultilinearKZGProver::generate_proof(
+                MultilinearKZGProver::generate_proof_with_commitment(
                     *opening,
                     encrypted_lagrange_basis,
                     &running_layer_polynomial,
+                    input_commitment,
                 )
             })
             .collect::<Vec<_>>();
@@ -1,3 +1,4 @@
+use arithmetic_circuit::circuit::Circuit;
 use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 
 use ark_ff::PrimeField;
@@ -13,6 +14,28 @@ pub fn get_folded_claim_sum<T: PrimeField>(
     (*w_i_b_eval * *alpha) + (*w_i_c_eval * *beta)
 }
 
+// Unwraps a fully assigned challenge vector, returning the index of the first missing challenge otherwise
+pub fn unwrap_challenges<T: PrimeField>(points: &[Option<T>]) -> Result<Vec<T>, usize> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(idx, point)| point.ok_or(idx))
+        .collect()
+}
+
+// GKR's sumcheck splits each gate poly into exactly a, b and c, so it only supports circuits whose layers all have fan-in 2
+// Returns the first layer (0 being the output layer) that doesn't, alongside the fan-in of its first gate that isn't 2.
+// Goes through the gates directly, so a layer mixing fan-ins is reported instead of hitting layer_fan_in's panic.
+pub fn find_unsupported_fan_in<T: PrimeField>(circuit: &Circuit<T>) -> Option<(usize, usize)> {
+    (0..circuit.get_layer_count()).find_map(|layer_idx| {
+        circuit
+            .gate_fan_ins(layer_idx)
+            .into_iter()
+            .find(|fan_in| *fan_in != 2)
+            .map(|fan_in| (layer_idx, fan_in))
+    })
+}
+
 pub fn get_folded_polys<T: PrimeField>(
     alpha: &T,
     beta: &T,
@@ -33,11 +56,21 @@ pub fn get_folded_polys<T: PrimeField>(
         eval_points_rc[idx] = r_c[idx];
     });
 
-    let new_muli_b_c = (muli_a_b_c.evaluate(&eval_points_rb).scalar_mul(*alpha))
-        + (muli_a_b_c.evaluate(&eval_points_rc).scalar_mul(*beta));
+    let new_muli_b_c = MultiLinearPolynomial::linear_combination(
+        &[
+            muli_a_b_c.evaluate(&eval_points_rb),
+            muli_a_b_c.evaluate(&eval_points_rc),
+        ],
+        &[*alpha, *beta],
+    );
 
-    let new_addi_b_c = (addi_a_b_c.evaluate(&eval_points_rb).scalar_mul(*alpha))
-        + (addi_a_b_c.evaluate(&eval_points_rc).scalar_mul(*beta));
+    let new_addi_b_c = MultiLinearPolynomial::linear_combination(
+        &[
+            addi_a_b_c.evaluate(&eval_points_rb),
+            addi_a_b_c.evaluate(&eval_points_rc),
+        ],
+        &[*alpha, *beta],
+    );
 
     (new_muli_b_c, new_addi_b_c)
 }
@@ -63,3 +96,57 @@ pub fn get_evaluated_muli_addi_at_a<T: PrimeField>(
         addi_a_b_c.evaluate(&evaluation_points),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ark_bn254::Fq;
+
+    #[test]
+    pub fn test_unwrap_challenges() {
+        assert_eq!(
+            unwrap_challenges(&[Some(Fq::from(3)), Some(Fq::from(5)), Some(Fq::from(7))]),
+            Ok(vec![Fq::from(3), Fq::from(5), Fq::from(7)])
+        );
+
+        assert_eq!(
+            unwrap_challenges(&[
+                Some(Fq::from(3)),
+                Some(Fq::from(5)),
+                None,
+                Some(Fq::from(7))
+            ]),
+            Err(2)
+        );
+    }
+
+    #[test]
+    pub fn test_evaluated_muli_addi_at_a_for_single_gate_output() {
+        // a single mul gate over inputs 0 and 1: 1 (padded) bit for a, 1 bit each for b and c
+        let muli_a_b_c = MultiLinearPolynomial::new(&vec![
+            Fq::from(0),
+            Fq::from(1),
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(0),
+        ]);
+        let addi_a_b_c = MultiLinearPolynomial::new(&vec![Fq::from(0); 8]);
+
+        // the output W poly has 2 evaluations, so GKR draws a single random value for a
+        let r_a = Fq::from(5);
+        let (muli_b_c, addi_b_c) =
+            get_evaluated_muli_addi_at_a(muli_a_b_c.clone(), addi_a_b_c, &[Some(r_a)]);
+
+        // the None padding leaves exactly b and c free
+        assert_eq!(muli_b_c.number_of_variables(), 2);
+        assert_eq!(addi_b_c.number_of_variables(), 2);
+        assert_eq!(
+            muli_b_c.get_evaluation_points(),
+            &vec![Fq::from(0), Fq::from(1) - r_a, Fq::from(0), Fq::from(0)]
+        );
+    }
+}
@@ -2,16 +2,54 @@ use arithmetic_circuit::circuit::Circuit;
 use fiat_shamir::transcript::Transcript;
 use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 use std::hash::Hash;
+use sumcheck::sumcheck_protocol::SumCheckProof;
 use sumcheck::verifier::SumcheckVerifier;
 
 use crate::gkr_protocol::{GKRProof, GKRProofWithKZG};
-use crate::utils::{get_evaluated_muli_addi_at_a, get_folded_polys};
+use crate::utils::{
+    find_unsupported_fan_in, get_evaluated_muli_addi_at_a, get_folded_polys, unwrap_challenges,
+};
 
 use ark_ec::pairing::Pairing;
 use ark_ff::{BigInteger, PrimeField};
-use kzg::multilinear::verifier::MultilinearKZGVerifier;
+use kzg::multilinear::verifier::{KzgVerifyError, MultilinearKZGVerifier};
 use std::marker::PhantomData;
 
+#[derive(Debug, PartialEq)]
+pub enum GKRVerifyError {
+    // The output poly doesn't match the circuit's output layer
+    OutputWidthMismatch,
+    // The proof doesn't have the shape the circuit requires (number of sumcheck proofs, rounds, w evals or kzg proofs)
+    MalformedProof,
+    // The circuit has a layer GKR can't prove: (layer index, 0 being the output layer, and the first fan-in other than 2 in it)
+    UnsupportedFanIn(usize, usize),
+    // The output poly isn't the circuit's output on the inputs (see verify_proof_with_inputs)
+    OutputMismatch,
+    // The sumcheck proof or the f_b_c oracle check failed at the given layer
+    LayerCheckFailed(usize),
+    // The KZG opening of the input poly failed (0 => opening at r_b, 1 => opening at r_c)
+    InputOpeningFailed(usize, KzgVerifyError),
+}
+
+// What the layer by layer verifier carries between layers: the transcript, the challenges the next layer is checked at,
+// and how many layers have been verified so far
+pub struct VerifierState<'a, T: PrimeField> {
+    transcript: &'a mut Transcript<T>,
+    random_values: Vec<Option<T>>,
+    layers_verified: usize,
+}
+
+impl<T: PrimeField> VerifierState<'_, T> {
+    pub fn layers_verified(&self) -> usize {
+        self.layers_verified
+    }
+
+    // Whether every layer of the circuit has been verified, i.e. the proof is accepted
+    pub fn is_complete(&self, circuit: &Circuit<T>) -> bool {
+        self.layers_verified == circuit.get_layer_count()
+    }
+}
+
 pub struct GKRVerifier<T: PrimeField, P: Pairing> {
     _marker: PhantomData<T>,
     _marker2: PhantomData<P>,
@@ -24,98 +62,218 @@ impl<T: PrimeField, P: Pairing> GKRVerifier<T, P> {
         transcript: &mut Transcript<T>,
         proof: GKRProof<T>,
     ) -> bool {
+        Self::verify_layers(initial_inputs, circuit, transcript, &proof).is_ok()
+    }
+
+    // Opt-in check for verifiers that can afford to run the circuit themselves (i.e. the computation isn't delegated):
+    // the output poly is compared against the circuit's actual output before any sumcheck work,
+    // so a prover lying about the output is caught immediately. The rest is the same verification as verify_proof
+    pub fn verify_proof_with_inputs(
+        initial_inputs: &[T],
+        circuit: &mut Circuit<T>,
+        transcript: &mut Transcript<T>,
+        proof: GKRProof<T>,
+    ) -> Result<(), GKRVerifyError> {
+        let output_layer = circuit
+            .evaluate_at_input(initial_inputs.to_vec())
+            .pop()
+            .ok_or(GKRVerifyError::MalformedProof)?;
+
+        if output_layer.get_evaluation_points() != proof.output_poly.get_evaluation_points() {
+            return Err(GKRVerifyError::OutputMismatch);
+        }
+
+        Self::verify_layers(initial_inputs, circuit, transcript, &proof)
+    }
+
+    // The succinct verification behind verify_proof and verify_proof_with_inputs:
+    // checks the proof's shape against the circuit, then runs every layer through verify_layer
+    fn verify_layers(
+        initial_inputs: &[T],
+        circuit: &Circuit<T>,
+        transcript: &mut Transcript<T>,
+        proof: &GKRProof<T>,
+    ) -> Result<(), GKRVerifyError> {
+        // reject proofs whose shape doesn't match the circuit, so indexing into them below can't go out of bounds
+        // there's one sumcheck proof per layer, and w evals for every layer but the input one
+        if proof.sumcheck_proofs.len() != circuit.get_layer_count()
+            || proof.w_polys_evals.len() + 1 != circuit.get_layer_count()
+        {
+            return Err(GKRVerifyError::MalformedProof);
+        }
+
+        let mut state = Self::begin_verification(circuit, transcript, &proof.output_poly)?;
+
+        for (layer_idx, sumcheck_proof) in proof.sumcheck_proofs.iter().enumerate() {
+            state = Self::verify_layer(
+                state,
+                circuit,
+                layer_idx,
+                sumcheck_proof,
+                proof.w_polys_evals.get(layer_idx).copied(),
+                initial_inputs,
+            )?;
+        }
+
+        if !state.is_complete(circuit) {
+            return Err(GKRVerifyError::MalformedProof);
+        }
+
+        Ok(())
+    }
+
+    // Starts verifying a proof layer by layer (e.g. one streamed by GKRProver::generate_proof_streaming)
+    // Checks the output poly against the circuit and binds it to the transcript, then each layer goes through verify_layer
+    pub fn begin_verification<'a>(
+        circuit: &Circuit<T>,
+        transcript: &'a mut Transcript<T>,
+        output_poly: &MultiLinearPolynomial<T>,
+    ) -> Result<VerifierState<'a, T>, GKRVerifyError> {
+        // reject output polys that don't match the circuit's output layer before doing any work
+        if output_poly.get_evaluation_points().len() != circuit.output_width() {
+            return Err(GKRVerifyError::OutputWidthMismatch);
+        }
+
+        if let Some((layer_idx, fan_in)) = find_unsupported_fan_in(circuit) {
+            return Err(GKRVerifyError::UnsupportedFanIn(layer_idx, fan_in));
+        }
+
         // performs the same step as prover in output poly
-        let length_of_rs = proof.output_poly.number_of_variables();
+        let length_of_rs = output_poly.number_of_variables();
 
-        transcript.append(&proof.output_poly.to_bytes());
+        transcript.append(b"GKR");
+        transcript.append_u64(circuit.get_layer_count() as u64);
+        transcript.append_multilinear(output_poly);
 
-        let mut random_values: Vec<Option<T>> = transcript
+        let random_values: Vec<Option<T>> = transcript
             .sample_n_challenges(length_of_rs as usize)
             .into_iter()
             .map(|challenge| Some(challenge))
             .collect();
 
-        for layer_idx in 0..circuit.get_layer_count() {
-            let muli_a_b_c = circuit.get_mul_i(layer_idx);
-            let addi_a_b_c = circuit.get_add_i(layer_idx);
+        Ok(VerifierState {
+            transcript,
+            random_values,
+            layers_verified: 0,
+        })
+    }
 
-            let (new_muli_b_c, new_addi_b_c) = match layer_idx {
-                0 => get_evaluated_muli_addi_at_a(muli_a_b_c, addi_a_b_c, &random_values),
-                _ => {
-                    let (alpha, beta) =
-                        (transcript.sample_challenge(), transcript.sample_challenge());
+    // Verifies the next layer's sumcheck proof, given the prover's w evals for it (None for the input layer,
+    // whose evaluations the verifier computes from the inputs). Layers must come in order, starting at the output layer.
+    // Running this over every layer, then checking the state is_complete, accepts exactly the proofs verify_proof does.
+    pub fn verify_layer<'a>(
+        state: VerifierState<'a, T>,
+        circuit: &Circuit<T>,
+        layer_idx: usize,
+        sumcheck_proof: &SumCheckProof<T>,
+        w_evals: Option<(T, T)>,
+        initial_inputs: &[T],
+    ) -> Result<VerifierState<'a, T>, GKRVerifyError> {
+        let VerifierState {
+            transcript,
+            random_values,
+            layers_verified,
+        } = state;
+
+        if layer_idx != layers_verified || layer_idx >= circuit.get_layer_count() {
+            return Err(GKRVerifyError::MalformedProof);
+        }
 
-                    // Get the new addi's and muli's using alpha beta folding.
-                    let (new_muli_b_c, new_addi_b_c) = get_folded_polys(
-                        &alpha,
-                        &beta,
-                        muli_a_b_c,
-                        addi_a_b_c,
-                        &random_values[0..random_values.len() / 2],
-                        &random_values[random_values.len() / 2..],
-                    );
+        let (addi_a_b_c, muli_a_b_c) = (circuit.get_add_i(layer_idx), circuit.get_mul_i(layer_idx));
 
-                    (new_muli_b_c, new_addi_b_c)
-                }
-            };
+        let (new_muli_b_c, new_addi_b_c) = match layer_idx {
+            0 => get_evaluated_muli_addi_at_a(muli_a_b_c, addi_a_b_c, &random_values),
+            _ => {
+                let (alpha, beta) = (transcript.sample_challenge(), transcript.sample_challenge());
 
-            // Partial verifier checks if partial proof is correct and returns final claim sum and next r values in the process
-            let (is_verified, final_claim_sum, next_evaluation_values) =
-                SumcheckVerifier::partial_verify(&proof.sumcheck_proofs[layer_idx], transcript);
+                // Get the new addi's and muli's using alpha beta folding.
+                let (new_muli_b_c, new_addi_b_c) = get_folded_polys(
+                    &alpha,
+                    &beta,
+                    muli_a_b_c,
+                    addi_a_b_c,
+                    &random_values[0..random_values.len() / 2],
+                    &random_values[random_values.len() / 2..],
+                );
 
-            // Using the next set of rs gotten from partial prover, we evaluate the new addi's and muli's
-            let evaluated_addi_b_c = new_addi_b_c.evaluate(&next_evaluation_values);
-            let evaluated_muli_b_c = new_muli_b_c.evaluate(&next_evaluation_values);
+                (new_muli_b_c, new_addi_b_c)
+            }
+        };
 
-            let (new_addi_b_c_eval, new_muli_b_c_eval) = (
-                evaluated_addi_b_c.get_evaluation_points().first().unwrap(),
-                evaluated_muli_b_c.get_evaluation_points().first().unwrap(),
+        // the sumcheck must run exactly one round per variable of f_b_c
+        if sumcheck_proof.round_polys.len() != new_addi_b_c.number_of_variables() as usize {
+            return Err(GKRVerifyError::MalformedProof);
+        }
+
+        // Partial verifier checks if partial proof is correct and returns final claim sum and next r values in the process
+        let (is_verified, final_claim_sum, next_evaluation_values) =
+            SumcheckVerifier::partial_verify(
+                sumcheck_proof,
+                transcript,
+                new_addi_b_c.number_of_variables() as usize,
             );
 
-            // Once we get to the layer before the input, we use the input polynomial instead to build the next_w_i evals,
-            let (next_w_i_b_eval, next_w_i_c_eval) = if layer_idx + 1 == circuit.get_layer_count() {
-                let (r_b, r_c) = next_evaluation_values.split_at(next_evaluation_values.len() / 2);
+        // a failed round stops early with fewer challenges than variables, so bail out before evaluating at them
+        if !is_verified {
+            return Err(GKRVerifyError::LayerCheckFailed(layer_idx));
+        }
 
-                let next_w_i = MultiLinearPolynomial::new(&Vec::from(initial_inputs));
+        // Using the next set of rs gotten from partial prover, we evaluate the new addi's and muli's
+        let evaluated_addi_b_c = new_addi_b_c.evaluate(&next_evaluation_values);
+        let evaluated_muli_b_c = new_muli_b_c.evaluate(&next_evaluation_values);
 
-                (
-                    next_w_i
-                        .evaluate(r_b)
-                        .get_evaluation_points()
-                        .first()
-                        .unwrap()
-                        .clone(),
-                    next_w_i
-                        .evaluate(r_c)
-                        .get_evaluation_points()
-                        .first()
-                        .unwrap()
-                        .clone(),
-                )
-            // else use the w_poly evals the prover gives us
-            } else {
-                proof.w_polys_evals[layer_idx]
-            };
+        let (new_addi_b_c_eval, new_muli_b_c_eval) = (
+            evaluated_addi_b_c.get_evaluation_points().first().unwrap(),
+            evaluated_muli_b_c.get_evaluation_points().first().unwrap(),
+        );
 
-            // commit w's evaluated at rb and rc
+        let is_input_layer = layer_idx + 1 == circuit.get_layer_count();
+
+        // Once we get to the layer before the input, we use the input polynomial instead to build the next_w_i evals,
+        let (next_w_i_b_eval, next_w_i_c_eval) = if is_input_layer {
+            let (r_b, r_c) = next_evaluation_values.split_at(next_evaluation_values.len() / 2);
+
+            // pad the inputs the way the prover did, after which they must describe a polynomial over exactly the variables of r_b
+            let padded_inputs = Circuit::pad_inputs(initial_inputs);
+
+            if padded_inputs.len() != 1 << r_b.len() {
+                return Err(GKRVerifyError::MalformedProof);
+            }
+
+            let next_w_i = MultiLinearPolynomial::new(&padded_inputs);
+
+            (
+                next_w_i.evaluate(r_b).get_evaluation_points()[0],
+                next_w_i.evaluate(r_c).get_evaluation_points()[0],
+            )
+        // else use the w_poly evals the prover gives us
+        } else {
+            w_evals.ok_or(GKRVerifyError::MalformedProof)?
+        };
+
+        // commit w's evaluated at rb and rc
+        // the input layer's evaluations are never committed by the prover, since no challenge is drawn after them
+        if !is_input_layer {
             transcript.append_n(&[
                 &next_w_i_b_eval.into_bigint().to_bytes_le(),
                 &next_w_i_c_eval.into_bigint().to_bytes_le(),
             ]);
+        }
 
-            let fbc_eval = (*new_addi_b_c_eval * (next_w_i_b_eval + next_w_i_c_eval))
-                + (*new_muli_b_c_eval * (next_w_i_b_eval * next_w_i_c_eval));
-
-            // Now the verifier performs the oracle check not being handled by partial verifier
-            // We check if the f_b_c polynomial evaluated at b and c values equal the final claim sum
-            if !is_verified || (fbc_eval != final_claim_sum) {
-                return false;
-            }
+        let fbc_eval = (*new_addi_b_c_eval * (next_w_i_b_eval + next_w_i_c_eval))
+            + (*new_muli_b_c_eval * (next_w_i_b_eval * next_w_i_c_eval));
 
-            random_values = next_evaluation_values;
+        // Now the verifier performs the oracle check not being handled by partial verifier
+        // We check if the f_b_c polynomial evaluated at b and c values equal the final claim sum
+        if fbc_eval != final_claim_sum {
+            return Err(GKRVerifyError::LayerCheckFailed(layer_idx));
         }
 
-        true
+        Ok(VerifierState {
+            transcript,
+            random_values: next_evaluation_values,
+            layers_verified: layers_verified + 1,
+        })
     }
 
     // TODO: Add doc comments
@@ -125,14 +283,42 @@ impl<T: PrimeField, P: Pairing> GKRVerifier<T, P> {
         proof: GKRProofWithKZG<T, P>,
         encrypted_taus: &[P::G2],
     ) -> bool {
+        Self::verify_proof_with_kzg_detailed(circuit, transcript, proof, encrypted_taus).is_ok()
+    }
+
+    // Same as verify_proof_with_kzg, but returns where the proof was rejected
+    pub fn verify_proof_with_kzg_detailed(
+        circuit: &mut Circuit<T>,
+        transcript: &mut Transcript<T>,
+        proof: GKRProofWithKZG<T, P>,
+        encrypted_taus: &[P::G2],
+    ) -> Result<(), GKRVerifyError> {
+        // reject output polys that don't match the circuit's output layer before doing any work
+        if proof.output_poly.get_evaluation_points().len() != circuit.output_width() {
+            return Err(GKRVerifyError::OutputWidthMismatch);
+        }
+
+        if let Some((layer_idx, fan_in)) = find_unsupported_fan_in(circuit) {
+            return Err(GKRVerifyError::UnsupportedFanIn(layer_idx, fan_in));
+        }
+
+        // reject proofs whose shape doesn't match the circuit, so indexing into them below can't go out of bounds
+        // there's one sumcheck proof per layer, w evals for every layer but the input one, and an opening at both r_b and r_c
+        if proof.sumcheck_proofs.len() != circuit.get_layer_count()
+            || proof.w_polys_evals.len() + 1 != circuit.get_layer_count()
+            || proof.kzg_proofs.len() != 2
+        {
+            return Err(GKRVerifyError::MalformedProof);
+        }
+
         // performs the same step as prover in output poly
         let length_of_rs = proof.output_poly.number_of_variables();
 
-        // commit the commitment first before anything
-        transcript.append_n(&[
-            &proof.commitment.to_string().as_bytes(),
-            &proof.output_poly.to_bytes(),
-        ]);
+        // tag the transcript as GKR's, then commit the commitment first before anything
+        transcript.append(b"GKR");
+        transcript.append(proof.commitment.to_string().as_bytes());
+        transcript.append_u64(circuit.get_layer_count() as u64);
+        transcript.append_multilinear(&proof.output_poly);
 
         let mut random_values: Vec<Option<T>> = transcript
             .sample_n_challenges(length_of_rs as usize)
@@ -140,10 +326,9 @@ impl<T: PrimeField, P: Pairing> GKRVerifier<T, P> {
             .map(|challenge| Some(challenge))
             .collect();
 
-        for layer_idx in 0..circuit.get_layer_count() {
-            let muli_a_b_c = circuit.get_mul_i(layer_idx);
-            let addi_a_b_c = circuit.get_add_i(layer_idx);
-
+        for (layer_idx, (addi_a_b_c, muli_a_b_c)) in
+            circuit.all_gate_polys().into_iter().enumerate()
+        {
             let (new_muli_b_c, new_addi_b_c) = match layer_idx {
                 0 => get_evaluated_muli_addi_at_a(muli_a_b_c, addi_a_b_c, &random_values),
                 _ => {
@@ -164,9 +349,25 @@ impl<T: PrimeField, P: Pairing> GKRVerifier<T, P> {
                 }
             };
 
+            // the sumcheck must run exactly one round per variable of f_b_c
+            if proof.sumcheck_proofs[layer_idx].round_polys.len()
+                != new_addi_b_c.number_of_variables() as usize
+            {
+                return Err(GKRVerifyError::MalformedProof);
+            }
+
             // Partial verifier checks if partial proof is correct and returns final claim sum and next r values in the process
             let (is_verified, final_claim_sum, next_evaluation_values) =
-                SumcheckVerifier::partial_verify(&proof.sumcheck_proofs[layer_idx], transcript);
+                SumcheckVerifier::partial_verify(
+                    &proof.sumcheck_proofs[layer_idx],
+                    transcript,
+                    new_addi_b_c.number_of_variables() as usize,
+                );
+
+            // a failed round stops early with fewer challenges than variables, so bail out before evaluating at them
+            if !is_verified {
+                return Err(GKRVerifyError::LayerCheckFailed(layer_idx));
+            }
 
             // Using the next set of rs gotten from partial prover, we evaluate the new addi's and muli's
             let evaluated_addi_b_c = new_addi_b_c.evaluate(&next_evaluation_values);
@@ -177,74 +378,60 @@ impl<T: PrimeField, P: Pairing> GKRVerifier<T, P> {
                 evaluated_muli_b_c.get_evaluation_points().first().unwrap(),
             );
 
-            let (next_w_i_b_eval, next_w_i_c_eval, can_use_evals) =
-                if layer_idx + 1 == circuit.get_layer_count() {
-                    // Once we get to the layer before the input, we perform verify kzg proof on the input polynomial
-                    // This is to verify that the W_input evaluated value (V) is correct,
-                    let openings: Vec<T> = next_evaluation_values
-                        .to_vec()
-                        .iter()
-                        .map(|opening| opening.unwrap())
-                        .collect();
-                    let (r_b, r_c) = openings.split_at(openings.len() / 2);
-                    let mut are_proofs_correct = true;
-
-                    let input_evals = proof
-                        .kzg_proofs
-                        .iter()
-                        .enumerate()
-                        .map(|(idx, kzg_proof)| {
-                            let opening = match idx {
-                                0 => r_b,
-                                _ => r_c,
-                            };
-
-                            if !MultilinearKZGVerifier::verify_proof(
-                                &proof.commitment,
-                                kzg_proof,
-                                opening,
-                                encrypted_taus,
-                            ) {
-                                are_proofs_correct = false;
-                            }
-
-                            kzg_proof.v
-                        })
-                        .collect::<Vec<_>>();
-
-                    (input_evals[0], input_evals[1], are_proofs_correct)
-                    // else use the w_poly evals the prover gives us
-                } else {
-                    (
-                        proof.w_polys_evals[layer_idx].0,
-                        proof.w_polys_evals[layer_idx].1,
-                        true,
-                    )
-                };
-
-            // Once we figure out that we can't use the values, we go ahead and return false.
-            if !can_use_evals {
-                return false;
-            }
+            let (next_w_i_b_eval, next_w_i_c_eval) = if layer_idx + 1 == circuit.get_layer_count() {
+                // Once we get to the layer before the input, we perform verify kzg proof on the input polynomial
+                // This is to verify that the W_input evaluated value (V) is correct,
+                let openings = unwrap_challenges(&next_evaluation_values)
+                    .map_err(|_| GKRVerifyError::MalformedProof)?;
+                let (r_b, r_c) = openings.split_at(openings.len() / 2);
+
+                // we only get to use an input evaluation once its opening checks out
+                let mut input_evals = Vec::with_capacity(2);
+
+                for (idx, (kzg_proof, opening)) in
+                    proof.kzg_proofs.iter().zip([r_b, r_c]).enumerate()
+                {
+                    input_evals.push(
+                        MultilinearKZGVerifier::verify_and_get_value_detailed(
+                            &proof.commitment,
+                            kzg_proof,
+                            opening,
+                            encrypted_taus,
+                        )
+                        .map_err(|err| GKRVerifyError::InputOpeningFailed(idx, err))?,
+                    );
+                }
+
+                (input_evals[0], input_evals[1])
+                // else use the w_poly evals the prover gives us
+            } else {
+                (
+                    proof.w_polys_evals[layer_idx].0,
+                    proof.w_polys_evals[layer_idx].1,
+                )
+            };
 
             // commit w's evaluated at rb and rc
-            transcript.append_n(&[
-                &next_w_i_b_eval.into_bigint().to_bytes_le(),
-                &next_w_i_c_eval.into_bigint().to_bytes_le(),
-            ]);
+            // the input layer's evaluations are never committed by the prover, since no challenge is drawn after them
+            if layer_idx + 1 < circuit.get_layer_count() {
+                transcript.append_n(&[
+                    &next_w_i_b_eval.into_bigint().to_bytes_le(),
+                    &next_w_i_c_eval.into_bigint().to_bytes_le(),
+                ]);
+            }
 
             let fbc_eval = (*new_addi_b_c_eval * (next_w_i_b_eval + next_w_i_c_eval))
                 + (*new_muli_b_c_eval * (next_w_i_b_eval * next_w_i_c_eval));
 
             // Now the verifier performs the oracle check not being handled by partial verifier
             // We check if the f_b_c polynomial evaluated at b and c values equal the final claim sum
-            if !is_verified || (fbc_eval != final_claim_sum) {
-                return false;
+            if fbc_eval != final_claim_sum {
+                return Err(GKRVerifyError::LayerCheckFailed(layer_idx));
             }
 
             random_values = next_evaluation_values;
         }
 
-        true
+        Ok(())
     }
 }
@@ -3,8 +3,9 @@ mod tests {
     use crate::multilinear::trusted_setup::TrustedSetup;
 
     use crate::multilinear::prover::{MultilinearKZGProof, MultilinearKZGProver};
-    use crate::multilinear::verifier::MultilinearKZGVerifier;
+    use crate::multilinear::verifier::{KzgVerifyError, MultilinearKZGVerifier};
     use ark_bls12_381::{Bls12_381, Fr};
+    use fiat_shamir::transcript::Transcript;
     use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 
     #[test]
@@ -41,4 +42,320 @@ mod tests {
             &trusted_setup.encrypted_taus
         ));
     }
+
+    #[test]
+    pub fn test_kzg_protocol_detailed_errors() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let polynomial = MultiLinearPolynomial::new(&vec![
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(3),
+            Fr::from(7),
+        ]);
+        let openings = vec![Fr::from(6), Fr::from(4), Fr::from(0)];
+
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        let mut proof: MultilinearKZGProof<Fr, Bls12_381> = MultilinearKZGProver::generate_proof(
+            &openings,
+            &trusted_setup.encrypted_lagrange_basis,
+            &polynomial,
+        );
+
+        assert_eq!(
+            MultilinearKZGVerifier::verify_proof_detailed(
+                &commitment,
+                &proof,
+                &openings,
+                &trusted_setup.encrypted_taus
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            MultilinearKZGVerifier::verify_proof_detailed(
+                &commitment,
+                &proof,
+                &openings[0..2],
+                &trusted_setup.encrypted_taus
+            ),
+            Err(KzgVerifyError::OpeningLengthMismatch)
+        );
+
+        proof.v += Fr::from(1);
+
+        assert_eq!(
+            MultilinearKZGVerifier::verify_proof_detailed(
+                &commitment,
+                &proof,
+                &openings,
+                &trusted_setup.encrypted_taus
+            ),
+            Err(KzgVerifyError::PairingCheckFailed)
+        );
+    }
+
+    #[test]
+    pub fn test_kzg_protocol_batch_commitment() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let first_polynomial =
+            MultiLinearPolynomial::new(&(0..8u64).map(Fr::from).collect::<Vec<_>>());
+        let second_polynomial =
+            MultiLinearPolynomial::new(&(0..8u64).map(|i| Fr::from(i * i)).collect::<Vec<_>>());
+        let openings = vec![Fr::from(6), Fr::from(4), Fr::from(0)];
+
+        let (commitment, coefficients) = MultilinearKZGProver::<Fr, Bls12_381>::commit_batch(
+            &[&first_polynomial, &second_polynomial],
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+
+        // the verifier rebuilds the combination from the returned coefficients
+        let combined_polynomial = first_polynomial.scalar_mul(coefficients[0])
+            + second_polynomial.scalar_mul(coefficients[1]);
+
+        let proof: MultilinearKZGProof<Fr, Bls12_381> = MultilinearKZGProver::generate_proof(
+            &openings,
+            &trusted_setup.encrypted_lagrange_basis,
+            &combined_polynomial,
+        );
+
+        assert!(MultilinearKZGVerifier::verify_proof(
+            &commitment,
+            &proof,
+            &openings,
+            &trusted_setup.encrypted_taus
+        ));
+
+        let (reordered_commitment, _) = MultilinearKZGProver::<Fr, Bls12_381>::commit_batch(
+            &[&second_polynomial, &first_polynomial],
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+
+        assert_ne!(commitment, reordered_commitment);
+    }
+
+    #[test]
+    pub fn test_kzg_verify_and_get_value() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let polynomial = MultiLinearPolynomial::new(&vec![
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(3),
+            Fr::from(7),
+        ]);
+        let openings = vec![Fr::from(6), Fr::from(4), Fr::from(0)];
+
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        let mut proof: MultilinearKZGProof<Fr, Bls12_381> = MultilinearKZGProver::generate_proof(
+            &openings,
+            &trusted_setup.encrypted_lagrange_basis,
+            &polynomial,
+        );
+
+        let expected_value = *polynomial
+            .evaluate(
+                &openings
+                    .iter()
+                    .map(|opening| Some(*opening))
+                    .collect::<Vec<_>>(),
+            )
+            .get_evaluation_points()
+            .first()
+            .unwrap();
+
+        assert_eq!(
+            MultilinearKZGVerifier::verify_and_get_value(
+                &commitment,
+                &proof,
+                &openings,
+                &trusted_setup.encrypted_taus
+            ),
+            Some(expected_value)
+        );
+
+        // a proof claiming the wrong value mustn't hand that value back
+        proof.v += Fr::from(1);
+
+        assert_eq!(
+            MultilinearKZGVerifier::verify_and_get_value(
+                &commitment,
+                &proof,
+                &openings,
+                &trusted_setup.encrypted_taus
+            ),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_kzg_equality_proof() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let polynomial = MultiLinearPolynomial::new(&(0..8u64).map(Fr::from).collect::<Vec<_>>());
+        let same_polynomial =
+            MultiLinearPolynomial::new(&(0..8u64).map(Fr::from).collect::<Vec<_>>());
+        let different_polynomial =
+            MultiLinearPolynomial::new(&(1..9u64).map(Fr::from).collect::<Vec<_>>());
+
+        let [commitment, same_commitment, different_commitment] =
+            [&polynomial, &same_polynomial, &different_polynomial].map(|poly| {
+                MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+                    poly,
+                    &trusted_setup.encrypted_lagrange_basis,
+                )
+            });
+
+        let proofs = MultilinearKZGProver::<Fr, Bls12_381>::prove_equality(
+            &commitment,
+            &polynomial,
+            &same_commitment,
+            &same_polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+
+        assert!(MultilinearKZGVerifier::verify_equality(
+            &commitment,
+            &same_commitment,
+            &proofs,
+            &trusted_setup.encrypted_taus,
+            &mut Transcript::new(),
+        ));
+
+        let proofs = MultilinearKZGProver::<Fr, Bls12_381>::prove_equality(
+            &commitment,
+            &polynomial,
+            &different_commitment,
+            &different_polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+
+        assert!(!MultilinearKZGVerifier::verify_equality(
+            &commitment,
+            &different_commitment,
+            &proofs,
+            &trusted_setup.encrypted_taus,
+            &mut Transcript::new(),
+        ));
+    }
+
+    #[test]
+    pub fn test_kzg_equality_proof_with_larger_setup() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        // the same taus, extended with one more: the verifier can hold a setup for bigger polynomials
+        let larger_trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3), Fr::from(7)]);
+        let polynomial = MultiLinearPolynomial::new(&(0..8u64).map(Fr::from).collect::<Vec<_>>());
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        let proofs = MultilinearKZGProver::<Fr, Bls12_381>::prove_equality(
+            &commitment,
+            &polynomial,
+            &commitment,
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+
+        assert!(MultilinearKZGVerifier::verify_equality(
+            &commitment,
+            &commitment,
+            &proofs,
+            &larger_trusted_setup.encrypted_taus,
+            &mut Transcript::new(),
+        ));
+    }
+
+    #[test]
+    pub fn test_kzg_equality_proof_rejects_different_variable_counts() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let smaller_trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2)]);
+        let polynomial = MultiLinearPolynomial::new(&(0..8u64).map(Fr::from).collect::<Vec<_>>());
+        let smaller_polynomial =
+            MultiLinearPolynomial::new(&(0..4u64).map(Fr::from).collect::<Vec<_>>());
+
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+        let smaller_commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &smaller_polynomial,
+            &smaller_trusted_setup.encrypted_lagrange_basis,
+        );
+
+        let (proof, _) = MultilinearKZGProver::<Fr, Bls12_381>::prove_equality(
+            &commitment,
+            &polynomial,
+            &commitment,
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+        let (_, smaller_proof) = MultilinearKZGProver::<Fr, Bls12_381>::prove_equality(
+            &smaller_commitment,
+            &smaller_polynomial,
+            &smaller_commitment,
+            &smaller_polynomial,
+            &smaller_trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+
+        assert!(!MultilinearKZGVerifier::verify_equality(
+            &commitment,
+            &smaller_commitment,
+            &(proof, smaller_proof),
+            &trusted_setup.encrypted_taus,
+            &mut Transcript::new(),
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "polynomials with 3 and 2 variables can't be proven equal")]
+    pub fn test_kzg_equality_proof_panics_on_different_variable_counts() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let polynomial = MultiLinearPolynomial::new(&(0..8u64).map(Fr::from).collect::<Vec<_>>());
+        let smaller_polynomial =
+            MultiLinearPolynomial::new(&(0..4u64).map(Fr::from).collect::<Vec<_>>());
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        MultilinearKZGProver::<Fr, Bls12_381>::prove_equality(
+            &commitment,
+            &polynomial,
+            &commitment,
+            &smaller_polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+            &mut Transcript::new(),
+        );
+    }
 }
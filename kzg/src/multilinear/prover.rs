@@ -1,3 +1,4 @@
+use fiat_shamir::transcript::Transcript;
 use polynomials::multilinear_polynomial::evaluation_form::{
     BlowUpDirection, MultiLinearPolynomial,
 };
@@ -5,7 +6,7 @@ use std::cmp::max;
 
 use ark_ec::pairing::Pairing;
 use ark_ec::PrimeGroup;
-use ark_ff::PrimeField;
+use ark_ff::{PrimeField, Zero};
 use std::marker::PhantomData;
 
 #[derive(Debug)]
@@ -41,6 +42,11 @@ impl<T: PrimeField, P: Pairing> MultilinearKZGProver<T, P> {
             panic!("Number of variables of polynomial does not match the number of Taus given!")
         };
 
+        // the zero polynomial commits to the identity, no need to scale every basis element by 0
+        if polynomial_evals.iter().all(|eval| eval.is_zero()) {
+            return P::G1::zero();
+        }
+
         let evaluation_points = (0..encrypted_lagrange_basis.len())
             .map(|i| encrypted_lagrange_basis[i].mul_bigint(polynomial_evals[i].into_bigint()))
             .collect::<Vec<_>>();
@@ -56,6 +62,59 @@ impl<T: PrimeField, P: Pairing> MultilinearKZGProver<T, P> {
         Self::evaluate_at_tau(f, encrypted_lagrange_basis)
     }
 
+    // Commits several polynomials (of the same number of variables) under one commitment
+    // The polynomials are absorbed into the transcript in order, and one combination coefficient per polynomial is sampled from it
+    // The commitment is to ∑ coefficient_i * poly_i, and the coefficients are returned so the verifier can reconstruct the combination
+    pub fn commit_batch(
+        polys: &[&MultiLinearPolynomial<T>],
+        encrypted_lagrange_basis: &[P::G1],
+        transcript: &mut Transcript<T>,
+    ) -> (P::G1, Vec<T>) {
+        if polys.is_empty() {
+            panic!("At least one polynomial is needed for a batch commitment");
+        }
+
+        polys
+            .iter()
+            .for_each(|poly| transcript.append_multilinear(poly));
+
+        let coefficients = transcript.sample_n_challenges(polys.len());
+
+        let combined_polynomial = polys
+            .iter()
+            .zip(&coefficients)
+            .map(|(poly, coefficient)| poly.scalar_mul(*coefficient))
+            .reduce(|acc, poly| acc + poly)
+            .unwrap();
+
+        (
+            Self::generate_commitment(&combined_polynomial, encrypted_lagrange_basis),
+            coefficients,
+        )
+    }
+
+    // Lifts the idx-th quotient back to the full number of variables and evaluates it at tau
+    fn quotient_at_tau(
+        quotient: &[T],
+        idx: usize,
+        number_of_openings: usize,
+        encrypted_lagrange_basis: &[P::G1],
+    ) -> P::G1 {
+        let blown_up_quotient = MultiLinearPolynomial::blow_up_n_times(
+            BlowUpDirection::Left,
+            max(
+                idx + 1,
+                number_of_openings - quotient.len().ilog2() as usize,
+            ),
+            quotient,
+        );
+
+        Self::evaluate_at_tau(
+            &MultiLinearPolynomial::new(&blown_up_quotient),
+            encrypted_lagrange_basis,
+        )
+    }
+
     pub fn generate_proof(
         openings: &[T],
         encrypted_lagrange_basis: &[P::G1],
@@ -74,26 +133,74 @@ impl<T: PrimeField, P: Pairing> MultilinearKZGProver<T, P> {
             // divide the polynomial by each opening as a factor
             // e.g. if the roots are a = 6, b = 7, c = 0; we divide the polynomial by a - 6, remainder by b - 7 and lastly, c - 0;
             // But in actual fact, we are evaluating the polynomial at the variable points.
-            let (mut quotient, remainder) = dividend.compute_quotient_remainder(opening, 0);
+            let quotient = dividend.compute_quotient(0);
 
-            dividend = remainder;
+            // The last remainder is f - v evaluated at every opening, which is always zero
+            // (equivalently, f's own final remainder is v_poly), so there's no need to compute it
+            if idx + 1 < openings.len() {
+                dividend = dividend.partially_evaluate((0, *opening));
+            }
 
-            quotient = MultiLinearPolynomial::blow_up_n_times(
-                BlowUpDirection::Left,
-                max(idx + 1, openings.len() - quotient.len().ilog2() as usize),
+            quotients.push(Self::quotient_at_tau(
                 &quotient,
-            );
-
-            let quotient_at_tau: P::G1 = Self::evaluate_at_tau(
-                &MultiLinearPolynomial::new(&quotient),
+                idx,
+                openings.len(),
                 encrypted_lagrange_basis,
-            );
-
-            quotients.push(quotient_at_tau);
+            ));
         }
 
         MultilinearKZGProof::new(*v_poly.get_evaluation_points().first().unwrap(), quotients)
     }
+
+    // Proves two committed polynomials are equal without revealing them:
+    // both commitments are absorbed, a random point is sampled from the transcript and both polynomials are opened there
+    // By Schwartz-Zippel, two different multilinear polynomials agree at a random point with negligible probability
+    // The number of variables is bound before the point is sampled, and is what the verifier samples the point's length from
+    pub fn prove_equality(
+        commitment_1: &P::G1,
+        polynomial_1: &MultiLinearPolynomial<T>,
+        commitment_2: &P::G1,
+        polynomial_2: &MultiLinearPolynomial<T>,
+        encrypted_lagrange_basis: &[P::G1],
+        transcript: &mut Transcript<T>,
+    ) -> (MultilinearKZGProof<T, P>, MultilinearKZGProof<T, P>) {
+        if polynomial_1.number_of_variables() != polynomial_2.number_of_variables() {
+            panic!(
+                "polynomials with {} and {} variables can't be proven equal",
+                polynomial_1.number_of_variables(),
+                polynomial_2.number_of_variables()
+            );
+        }
+
+        let number_of_variables = polynomial_1.number_of_variables() as usize;
+
+        transcript.append(commitment_1.to_string().as_bytes());
+        transcript.append(commitment_2.to_string().as_bytes());
+        transcript.append_u64(number_of_variables as u64);
+
+        let point = transcript.sample_n_challenges(number_of_variables);
+
+        (
+            Self::generate_proof(&point, encrypted_lagrange_basis, polynomial_1),
+            Self::generate_proof(&point, encrypted_lagrange_basis, polynomial_2),
+        )
+    }
+
+    // Same as generate_proof, for callers that already committed to the polynomial (e.g. GKR commits the input poly once).
+    // The proof itself doesn't depend on the commitment; in debug builds we check the supplied one matches the polynomial.
+    pub fn generate_proof_with_commitment(
+        openings: &[T],
+        encrypted_lagrange_basis: &[P::G1],
+        polynomial: &MultiLinearPolynomial<T>,
+        commitment: P::G1,
+    ) -> MultilinearKZGProof<T, P> {
+        debug_assert!(
+            Self::generate_commitment(polynomial, encrypted_lagrange_basis) == commitment,
+            "Supplied commitment does not match the polynomial"
+        );
+
+        Self::generate_proof(openings, encrypted_lagrange_basis, polynomial)
+    }
 }
 
 #[cfg(test)]
@@ -128,4 +235,118 @@ mod tests {
             G1Affine::generator().mul_bigint(Fr::from(42).into_bigint())
         )
     }
+
+    #[test]
+    pub fn test_commitment_to_zero_polynomial_is_identity() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &MultiLinearPolynomial::zero(3),
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        assert_eq!(commitment, <Bls12_381 as Pairing>::G1::zero());
+    }
+
+    #[test]
+    pub fn test_generate_proof_with_commitment() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let polynomial = MultiLinearPolynomial::new(&vec![
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(3),
+            Fr::from(7),
+        ]);
+        let openings = [Fr::from(6), Fr::from(4), Fr::from(0)];
+
+        let commitment = MultilinearKZGProver::<Fr, Bls12_381>::generate_commitment(
+            &polynomial,
+            &trusted_setup.encrypted_lagrange_basis,
+        );
+
+        let proof = MultilinearKZGProver::<Fr, Bls12_381>::generate_proof(
+            &openings,
+            &trusted_setup.encrypted_lagrange_basis,
+            &polynomial,
+        );
+        let proof_with_commitment =
+            MultilinearKZGProver::<Fr, Bls12_381>::generate_proof_with_commitment(
+                &openings,
+                &trusted_setup.encrypted_lagrange_basis,
+                &polynomial,
+                commitment,
+            );
+
+        assert_eq!(proof.v, proof_with_commitment.v);
+        assert_eq!(proof.q_taus, proof_with_commitment.q_taus);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Supplied commitment does not match the polynomial")]
+    pub fn test_generate_proof_with_wrong_commitment() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2)]);
+        let polynomial =
+            MultiLinearPolynomial::new(&vec![Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4)]);
+
+        MultilinearKZGProver::<Fr, Bls12_381>::generate_proof_with_commitment(
+            &[Fr::from(6), Fr::from(4)],
+            &trusted_setup.encrypted_lagrange_basis,
+            &polynomial,
+            G1Affine::generator().into_group(),
+        );
+    }
+
+    #[test]
+    pub fn test_generate_proof_skips_last_remainder() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+        let polynomial = MultiLinearPolynomial::new(&vec![
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(0),
+            Fr::from(4),
+            Fr::from(3),
+            Fr::from(7),
+        ]);
+        let openings = [Fr::from(6), Fr::from(4), Fr::from(0)];
+
+        let proof = MultilinearKZGProver::<Fr, Bls12_381>::generate_proof(
+            &openings,
+            &trusted_setup.encrypted_lagrange_basis,
+            &polynomial,
+        );
+
+        // divide all the way through, computing every remainder, both for f and f - v
+        let mut f_dividend = polynomial.clone();
+        let mut dividend = polynomial.minus(&proof.v);
+        let mut q_taus = vec![];
+
+        for (idx, opening) in openings.iter().enumerate() {
+            let (quotient, remainder) = dividend.compute_quotient_remainder(opening, 0);
+
+            q_taus.push(MultilinearKZGProver::<Fr, Bls12_381>::quotient_at_tau(
+                &quotient,
+                idx,
+                openings.len(),
+                &trusted_setup.encrypted_lagrange_basis,
+            ));
+
+            dividend = remainder;
+            f_dividend = f_dividend.compute_quotient_remainder(opening, 0).1;
+        }
+
+        assert_eq!(f_dividend.get_evaluation_points(), &vec![proof.v]);
+        assert_eq!(dividend.get_evaluation_points(), &vec![Fr::from(0)]);
+        assert_eq!(proof.q_taus, q_taus);
+    }
 }
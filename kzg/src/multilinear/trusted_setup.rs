@@ -19,10 +19,113 @@ impl<T: PrimeField, P: Pairing> TrustedSetup<T, P> {
             .map(|tau| P::G2::generator().mul_bigint(tau.into_bigint()))
             .collect::<Vec<_>>();
 
+        Self::from_parts(encrypted_taus, encrypted_lagrange_basis)
+    }
+
+    // Builds a setup from already encrypted parts (e.g. from a ceremony), checking they describe the same number of variables:
+    // one G2 tau per variable, and a G1 lagrange basis element per point of the hypercube over those variables
+    pub fn from_parts(encrypted_taus: Vec<P::G2>, encrypted_lagrange_basis: Vec<P::G1>) -> Self {
+        if encrypted_lagrange_basis.len() != 1 << encrypted_taus.len() {
+            panic!(
+                "{} encrypted taus need a lagrange basis of length {}, got {}",
+                encrypted_taus.len(),
+                1 << encrypted_taus.len(),
+                encrypted_lagrange_basis.len()
+            );
+        }
+
         Self {
             _marker: PhantomData,
             encrypted_taus,
             encrypted_lagrange_basis,
         }
     }
+
+    // The number of variables of the polynomials this setup can commit to
+    pub fn num_variables(&self) -> usize {
+        self.encrypted_taus.len()
+    }
+
+    // Sanity checks a setup (e.g. one loaded from untrusted bytes) before it gets used.
+    // The multilinear setup has no consecutive tau powers, so we check the analogous relation on the lagrange basis:
+    // for two basis values that only differ at variable i, L_1 = L * tau_i and L_0 + L_1 = L
+    // e(L_1, g2) === e(L_0 + L_1, g2^tau_i)
+    // The basis must also sum to g1, since the lagrange basis sums to 1
+    pub fn check_consistency(&self) -> bool {
+        let n = self.encrypted_taus.len();
+        let length_of_lagrange_basis = 1 << n;
+
+        if self.encrypted_lagrange_basis.len() != length_of_lagrange_basis {
+            return false;
+        }
+
+        if self.encrypted_lagrange_basis.iter().sum::<P::G1>() != P::G1::generator() {
+            return false;
+        }
+
+        let g2 = P::G2::generator();
+
+        self.encrypted_taus
+            .iter()
+            .enumerate()
+            .all(|(tau_idx, encrypted_tau)| {
+                let skip_value = 1 << (n - tau_idx - 1);
+
+                (0..length_of_lagrange_basis)
+                    .filter(|i| i & skip_value == 0)
+                    .all(|i| {
+                        let (l_0, l_1) = (
+                            self.encrypted_lagrange_basis[i],
+                            self.encrypted_lagrange_basis[i + skip_value],
+                        );
+
+                        P::pairing(l_1, g2) == P::pairing(l_0 + l_1, *encrypted_tau)
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr};
+
+    #[test]
+    pub fn test_check_consistency() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        assert!(trusted_setup.check_consistency());
+    }
+
+    #[test]
+    pub fn test_check_consistency_with_tampered_g2_element() {
+        let mut trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        trusted_setup.encrypted_taus[1] += <Bls12_381 as Pairing>::G2::generator();
+
+        assert!(!trusted_setup.check_consistency());
+    }
+
+    #[test]
+    pub fn test_num_variables() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        assert_eq!(trusted_setup.num_variables(), 3);
+        assert_eq!(trusted_setup.encrypted_lagrange_basis.len(), 1 << 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "2 encrypted taus need a lagrange basis of length 4, got 8")]
+    pub fn test_from_parts_with_mismatched_lengths() {
+        let trusted_setup: TrustedSetup<Fr, Bls12_381> =
+            TrustedSetup::new(&[Fr::from(5), Fr::from(2), Fr::from(3)]);
+
+        TrustedSetup::<Fr, Bls12_381>::from_parts(
+            trusted_setup.encrypted_taus[..2].to_vec(),
+            trusted_setup.encrypted_lagrange_basis,
+        );
+    }
 }
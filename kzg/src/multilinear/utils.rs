@@ -1,3 +1,5 @@
+use polynomials::multilinear_polynomial::evaluation_form::eq_polynomial_evals;
+
 use ark_ec::pairing::Pairing;
 use ark_ec::PrimeGroup;
 use ark_ff::PrimeField;
@@ -9,25 +11,7 @@ use ark_ff::PrimeField;
 // 010 => (1 - a) * (b) * (1 - c)
 // 111 => (a) * (b) * (c)
 pub fn generate_lagrange_basis_for_n_variables<T: PrimeField>(taus: &[T]) -> Vec<T> {
-    let n = taus.len();
-    let length_of_lagrange_basis = 1 << n;
-    let mut lagrange_basis = vec![T::one(); length_of_lagrange_basis];
-
-    for i in 0..length_of_lagrange_basis {
-        for (tau_idx, tau) in taus.iter().enumerate() {
-            let skip_value = 1 << (n - tau_idx - 1);
-
-            if (i / skip_value) % 2 == 0 {
-                // if bit at variable index is 0 (turned off), use check-zero (1 - x)
-                lagrange_basis[i] *= T::one() - *tau;
-            } else {
-                // else use check-one (x)
-                lagrange_basis[i] *= tau;
-            }
-        }
-    }
-
-    lagrange_basis
+    eq_polynomial_evals(taus)
 }
 
 // Encrypt lagrange basis by raising the generator to the power of each value in the basis
@@ -1,10 +1,21 @@
 use crate::multilinear::prover::MultilinearKZGProof;
+use fiat_shamir::transcript::Transcript;
 
 use ark_ec::pairing::Pairing;
 use ark_ec::PrimeGroup;
-use ark_ff::PrimeField;
+use ark_ff::{PrimeField, Zero};
 use std::marker::PhantomData;
 
+#[derive(Debug, PartialEq)]
+pub enum KzgVerifyError {
+    // The proof has no quotient contribution (constant polynomial), but the commitment isn't g1^v
+    CommitmentMismatch,
+    // f_tau - v doesn't match the sum of the quotients paired with (tau_i - opening_i)
+    PairingCheckFailed,
+    // The number of openings doesn't match the number of quotients or available taus
+    OpeningLengthMismatch,
+}
+
 pub struct MultilinearKZGVerifier<T: PrimeField, P: Pairing> {
     _marker: PhantomData<T>,
     _marker_2: PhantomData<P>,
@@ -17,12 +28,32 @@ impl<T: PrimeField, P: Pairing> MultilinearKZGVerifier<T, P> {
         openings: &[T],
         encrypted_taus: &[P::G2],
     ) -> bool {
+        Self::verify_proof_detailed(commitment, proof, openings, encrypted_taus).is_ok()
+    }
+
+    // Same as verify_proof, but returns the reason the proof was rejected
+    pub fn verify_proof_detailed(
+        commitment: &P::G1,
+        proof: &MultilinearKZGProof<T, P>,
+        openings: &[T],
+        encrypted_taus: &[P::G2],
+    ) -> Result<(), KzgVerifyError> {
+        if openings.len() != proof.q_taus.len() || openings.len() > encrypted_taus.len() {
+            return Err(KzgVerifyError::OpeningLengthMismatch);
+        }
+
         // The verifier checks that the LHS and RHS (that contains the proof) are equal
         //        L.H.S                     R.H.S
         //[   g1   ]   [g2]    [       g2        ]   [  g1  ]
         //f_tau - v  *  1  === ∑((tau_i - opening_i) * q_tau_i)
 
         let g1_v = P::G1::generator().mul_bigint(proof.v.into_bigint());
+
+        // with no quotients to account for, the check reduces to the commitment being g1^v
+        if proof.q_taus.iter().all(|q_tau| q_tau.is_zero()) && *commitment != g1_v {
+            return Err(KzgVerifyError::CommitmentMismatch);
+        }
+
         let f_tau_minus_v: P::G1 = *commitment - g1_v;
         let g2_1 = P::G2::generator().mul_bigint(T::one().into_bigint());
 
@@ -43,6 +74,64 @@ impl<T: PrimeField, P: Pairing> MultilinearKZGVerifier<T, P> {
         // ∑((tau_i - opening_i) * q_tau_i)
         let rhs = rhs_calc.iter().sum();
 
-        lhs == rhs
+        if lhs != rhs {
+            return Err(KzgVerifyError::PairingCheckFailed);
+        }
+
+        Ok(())
+    }
+
+    // Returns the opened value only if the proof checks out, so a failing proof's v can't be trusted by mistake
+    pub fn verify_and_get_value(
+        commitment: &P::G1,
+        proof: &MultilinearKZGProof<T, P>,
+        openings: &[T],
+        encrypted_taus: &[P::G2],
+    ) -> Option<T> {
+        Self::verify_and_get_value_detailed(commitment, proof, openings, encrypted_taus).ok()
+    }
+
+    // Same as verify_and_get_value, but returns the reason the proof was rejected
+    pub fn verify_and_get_value_detailed(
+        commitment: &P::G1,
+        proof: &MultilinearKZGProof<T, P>,
+        openings: &[T],
+        encrypted_taus: &[P::G2],
+    ) -> Result<T, KzgVerifyError> {
+        Self::verify_proof_detailed(commitment, proof, openings, encrypted_taus).map(|_| proof.v)
+    }
+
+    // Verifier side of MultilinearKZGProver::prove_equality
+    // Re-derives the random point from the commitments, checks both openings there and that they open to the same value
+    // The point has one challenge per variable, i.e. per quotient of the proofs, so proofs of different sizes are rejected
+    // (a setup with more taus than the polynomials have variables still verifies)
+    pub fn verify_equality(
+        commitment_1: &P::G1,
+        commitment_2: &P::G1,
+        proofs: &(MultilinearKZGProof<T, P>, MultilinearKZGProof<T, P>),
+        encrypted_taus: &[P::G2],
+        transcript: &mut Transcript<T>,
+    ) -> bool {
+        let number_of_variables = proofs.0.q_taus.len();
+
+        if proofs.1.q_taus.len() != number_of_variables
+            || number_of_variables > encrypted_taus.len()
+        {
+            return false;
+        }
+
+        transcript.append(commitment_1.to_string().as_bytes());
+        transcript.append(commitment_2.to_string().as_bytes());
+        transcript.append_u64(number_of_variables as u64);
+
+        let point = transcript.sample_n_challenges(number_of_variables);
+
+        match (
+            Self::verify_and_get_value(commitment_1, &proofs.0, &point, encrypted_taus),
+            Self::verify_and_get_value(commitment_2, &proofs.1, &point, encrypted_taus),
+        ) {
+            (Some(value_1), Some(value_2)) => value_1 == value_2,
+            _ => false,
+        }
     }
 }
@@ -1,5 +1,8 @@
+use crate::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
+
 use ark_ff::{BigInteger, PrimeField};
 use field_tracker::{end_tscope, start_tscope};
+use std::collections::BTreeMap;
 use std::ops::Add;
 
 #[derive(Debug)]
@@ -13,6 +16,12 @@ pub enum BlowUpDirection {
     Right,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum MultiLinearPolynomialError {
+    // The evaluation points length isn't a power of two, so it doesn't describe a whole hypercube
+    NonPowerOfTwoLength(usize),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MultiLinearPolynomial<T: PrimeField> {
     evaluation_points: Vec<T>,
@@ -31,6 +40,89 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         }
     }
 
+    // The polynomial over num_vars variables that is 0 everywhere
+    pub fn zero(num_vars: usize) -> Self {
+        Self::new(&vec![T::zero(); 1 << num_vars])
+    }
+
+    // Build a polynomial by evaluating f at every point of the boolean hypercube
+    // Points are visited in the same order as the evaluation points (first variable is the most significant bit)
+    // E.g. for 2 variables: [false, false], [false, true], [true, false], [true, true]
+    pub fn from_fn(num_vars: usize, f: impl Fn(&[bool]) -> T) -> Self {
+        let evaluation_points = hypercube_points(num_vars).map(|point| f(&point)).collect();
+
+        Self::new(&evaluation_points)
+    }
+
+    // Build a polynomial from the values at some points of the boolean hypercube, with every other point set to 0
+    // Points use the same variable order as the evaluation points (first variable is the most significant bit)
+    // Only hypercube points are supported: a multilinear polynomial is pinned down by its 2^n hypercube values,
+    // and values at arbitrary field points generally aren't consistent with any single multilinear polynomial
+    pub fn from_hypercube_pairs(num_vars: usize, pairs: &[(Vec<bool>, T)]) -> Self {
+        let mut evaluation_points = vec![T::zero(); 1 << num_vars];
+        let mut is_set = vec![false; 1 << num_vars];
+
+        pairs.iter().for_each(|(point, value)| {
+            if point.len() != num_vars {
+                panic!(
+                    "point has {} coordinates, expected {}",
+                    point.len(),
+                    num_vars
+                );
+            }
+
+            let idx = point
+                .iter()
+                .fold(0, |idx, bit| (idx << 1) | (*bit as usize));
+
+            if is_set[idx] {
+                panic!("point {:?} is given more than once", point);
+            }
+
+            evaluation_points[idx] = *value;
+            is_set[idx] = true;
+        });
+
+        Self::new(&evaluation_points)
+    }
+
+    // The multilinear extension of a vector of any length: the values are zero-padded to the next power of two
+    // Also returns the original length, so callers know which hypercube points are padding
+    pub fn extend_from_values(values: &[T]) -> (Self, usize) {
+        let mut evaluation_points = values.to_vec();
+        evaluation_points.resize(values.len().next_power_of_two(), T::zero());
+
+        (Self::new(&evaluation_points), values.len())
+    }
+
+    // The multilinear extension of eq(point, x), i.e. the polynomial whose evaluation points are eq_polynomial_evals(point)
+    // Its dot product with another polynomial's evaluation points is that polynomial evaluated at point,
+    // which lets a verifier without the plaintext polynomial (e.g. KZG-backed sumcheck) express the oracle check
+    pub fn eq_extension(point: &[T]) -> Self {
+        Self::new(&eq_polynomial_evals(point))
+    }
+
+    // Checks f(point) == <eq_extension(point), f's evaluation points>, the identity KZG and sumcheck lean on
+    // Meant as a debugging assertion for changes to evaluate or eq_extension, as it evaluates the polynomial both ways
+    pub fn verify_eq_identity(&self, point: &[T]) -> bool {
+        if point.len() != self.number_of_variables() as usize {
+            return false;
+        }
+
+        let evaluation = self
+            .evaluate(&point.iter().copied().map(Some).collect::<Vec<_>>())
+            .evaluation_points[0];
+
+        let dot_product = Self::eq_extension(point)
+            .evaluation_points
+            .iter()
+            .zip(&self.evaluation_points)
+            .map(|(eq_eval, eval)| *eq_eval * eval)
+            .sum::<T>();
+
+        evaluation == dot_product
+    }
+
     // Given the index where the bit in question is turned off, return flipped index
     fn get_flipped_bit_with_bitwise_or(
         &self,
@@ -148,16 +240,137 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         running_evaluation_points
     }
 
+    // This adds a new variable in front of the existing ones
+    // Its 0 branch is the current polynomial and its 1 branch is the given polynomial
+    // E.g. f(b) = [2, 3] extended with g(b) = [5, 7] => h(a, b) = [2, 3, 5, 7]
+    pub fn extend_variable(&self, high_branch: &MultiLinearPolynomial<T>) -> Self {
+        if self.number_of_variables() != high_branch.number_of_variables() {
+            panic!("Polynomial must have the same number of variables");
+        }
+
+        let mut extended_evaluation_points = self.evaluation_points.clone();
+        extended_evaluation_points.extend_from_slice(&high_branch.evaluation_points);
+
+        Self::new(&extended_evaluation_points)
+    }
+
+    // Inverse of extend_variable: returns the polynomial with its first variable fixed to 0 and to 1
+    // E.g. h(a, b) = [2, 3, 5, 7] => (f(b) = [2, 3], g(b) = [5, 7])
+    pub fn split_first_variable(&self) -> (MultiLinearPolynomial<T>, MultiLinearPolynomial<T>) {
+        if self.number_of_variables() == 0 {
+            panic!("Polynomial must have at least one variable");
+        }
+
+        let (low_branch, high_branch) = self
+            .evaluation_points
+            .split_at(self.evaluation_points.len() / 2);
+
+        (
+            Self::new(&low_branch.to_vec()),
+            Self::new(&high_branch.to_vec()),
+        )
+    }
+
+    // Relabels the variables so that result(x_0, ..., x_n-1) = self(x_perm[0], ..., x_perm[n-1])
+    // E.g. perm = [1, 0] on f(a, b) => g(a, b) = f(b, a)
+    pub fn permute_variables(&self, perm: &[usize]) -> Self {
+        let number_of_variables = self.number_of_variables() as usize;
+
+        let mut sorted_perm = perm.to_vec();
+        sorted_perm.sort();
+
+        if sorted_perm != (0..number_of_variables).collect::<Vec<_>>() {
+            panic!("perm must be a permutation of the variable indexes");
+        }
+
+        let bit_of = |point_idx: usize, variable_idx: usize| {
+            (point_idx >> (number_of_variables - 1 - variable_idx)) & 1
+        };
+
+        let permuted_evaluation_points = (0..self.evaluation_points.len())
+            .map(|point_idx| {
+                let original_idx = perm
+                    .iter()
+                    .fold(0, |acc, &idx| (acc << 1) | bit_of(point_idx, idx));
+
+                self.evaluation_points[original_idx]
+            })
+            .collect();
+
+        Self::new(&permuted_evaluation_points)
+    }
+
+    // Cyclically shifts the evaluation points, so the new polynomial's value at hypercube index i
+    // is this polynomial's value at index (i + shift) mod 2^n (the evaluation form analogue of a PLONK rotation)
+    pub fn rotate(&self, shift: usize) -> Self {
+        let mut evaluation_points = self.evaluation_points.clone();
+        let length = evaluation_points.len();
+
+        evaluation_points.rotate_left(shift % length);
+
+        Self::new(&evaluation_points)
+    }
+
     pub fn scalar_mul(&self, scalar: T) -> Self {
         Self::new(&self.evaluation_points.iter().map(|e| *e * scalar).collect())
     }
 
+    // Computes ∑ coeffs_i * polys_i in a single pass over the evaluation points
+    pub fn linear_combination(polys: &[MultiLinearPolynomial<T>], coeffs: &[T]) -> Self {
+        if polys.is_empty() || polys.len() != coeffs.len() {
+            panic!("linear combination needs one coefficient per polynomial");
+        }
+
+        let length = polys[0].evaluation_points.len();
+
+        if polys
+            .iter()
+            .any(|poly| poly.evaluation_points.len() != length)
+        {
+            panic!("polynomials in a linear combination must have the same number of variables");
+        }
+
+        Self::new(
+            &(0..length)
+                .map(|idx| {
+                    polys
+                        .iter()
+                        .zip(coeffs)
+                        .map(|(poly, coeff)| poly.evaluation_points[idx] * coeff)
+                        .sum()
+                })
+                .collect(),
+        )
+    }
+
     pub fn get_evaluation_points(&self) -> &Vec<T> {
         &self.evaluation_points
     }
 
+    // Moves the evaluation points out, for callers that are done with the polynomial and would otherwise clone them
+    pub fn into_evaluation_points(self) -> Vec<T> {
+        self.evaluation_points
+    }
+
+    // Assumes the evaluation points length is a power of two (which new enforces), and panics otherwise
     pub fn number_of_variables(&self) -> u32 {
-        self.evaluation_points.len().ilog2()
+        match self.try_number_of_variables() {
+            Ok(number_of_variables) => number_of_variables,
+            Err(MultiLinearPolynomialError::NonPowerOfTwoLength(length)) => panic!(
+                "Invalid Multilinear Polynomial: evaluation points length {} is not a power of two",
+                length
+            ),
+        }
+    }
+
+    pub fn try_number_of_variables(&self) -> Result<u32, MultiLinearPolynomialError> {
+        let length = self.evaluation_points.len();
+
+        if !length.is_power_of_two() {
+            return Err(MultiLinearPolynomialError::NonPowerOfTwoLength(length));
+        }
+
+        Ok(length.ilog2())
     }
 
     pub fn partially_evaluate(&self, variable: (usize, T)) -> Self {
@@ -191,6 +404,22 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         Self::new(&new_evaluation_points)
     }
 
+    // Specialised partially_evaluate for the last variable: its y1, y2 pairs are always adjacent,
+    // so we can fold them directly without working out the pairing indexes
+    pub fn fix_last_variable(&self, r: T) -> Self {
+        start_tscope!("Fix last variable");
+
+        let new_evaluation_points = self
+            .evaluation_points
+            .chunks_exact(2)
+            .map(|pair| pair[0] + ((pair[1] - pair[0]) * r))
+            .collect();
+
+        end_tscope!();
+
+        Self::new(&new_evaluation_points)
+    }
+
     pub fn evaluate(&self, points: &[Option<T>]) -> Self {
         start_tscope!("Evaluate polynomial");
 
@@ -205,7 +434,13 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
             |acc, (idx, point)| {
                 let mlp = match point {
                     Some(_) => {
-                        let new_acc = acc.partially_evaluate((idx - done, point.unwrap()));
+                        let variable_idx = idx - done;
+
+                        let new_acc = if variable_idx + 1 == acc.number_of_variables() as usize {
+                            acc.fix_last_variable(point.unwrap())
+                        } else {
+                            acc.partially_evaluate((variable_idx, point.unwrap()))
+                        };
                         done += 1;
 
                         new_acc
@@ -222,6 +457,52 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         evaluated_result
     }
 
+    // Fixes only the variables present in the assignment map and leaves the rest free.
+    // Variables are fixed from the highest index down, so earlier indexes are not shifted by a previous evaluation.
+    // E.g. {1: r1, 3: r3} on f(a, b, c, d) => f(a, r1, c, r3)
+    pub fn evaluate_sparse(&self, assignment: &BTreeMap<usize, T>) -> Self {
+        start_tscope!("Evaluate sparse polynomial");
+
+        if let Some((idx, _)) = assignment.last_key_value() {
+            if *idx >= self.number_of_variables() as usize {
+                panic!("variable index out of bounds");
+            }
+        }
+
+        let evaluated_result = assignment.iter().rev().fold(
+            MultiLinearPolynomial::new(&self.evaluation_points),
+            |acc, (idx, value)| acc.partially_evaluate((*idx, *value)),
+        );
+
+        end_tscope!();
+
+        evaluated_result
+    }
+
+    // Restricts the polynomial to the line through p0 and p1: g(t) = f(p0 + t * (p1 - p0)), so g(0) = f(p0) and g(1) = f(p1)
+    // f is multilinear, so g has degree at most the number of variables and is interpolated from that many + 1 points on the line
+    pub fn restrict_to_line(&self, p0: &[T], p1: &[T]) -> UnivariatePolynomial<T> {
+        let number_of_variables = self.number_of_variables() as usize;
+
+        if p0.len() != number_of_variables || p1.len() != number_of_variables {
+            panic!("line endpoints must have one coordinate per variable");
+        }
+
+        let evals = (0..=number_of_variables as u64)
+            .map(|t| {
+                let point = p0
+                    .iter()
+                    .zip(p1)
+                    .map(|(start, end)| Some(*start + T::from(t) * (*end - start)))
+                    .collect::<Vec<_>>();
+
+                self.evaluate(&point).get_evaluation_points()[0]
+            })
+            .collect::<Vec<_>>();
+
+        UnivariatePolynomial::from_hypercube_evaluations(&evals)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         // Convert evaluation points to a serializable format (e.g., bytes)
         let serializable_points: Vec<u8> = self
@@ -237,6 +518,28 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         self.evaluation_points.iter().sum()
     }
 
+    // Sums only the evaluation points whose bits agree with the fixed (variable index, bit) pairs.
+    // Variable 0 is the most significant bit of the evaluation index.
+    // E.g. [(0, true), (2, false)] on f(a, b, c, d) => sum of f(1, b, 0, d) over b, d in {0, 1}
+    pub fn sum_over_subcube(&self, fixed: &[(usize, bool)]) -> T {
+        let number_of_variables = self.number_of_variables() as usize;
+
+        if fixed.iter().any(|(idx, _)| *idx >= number_of_variables) {
+            panic!("variable index out of bounds");
+        }
+
+        self.evaluation_points
+            .iter()
+            .enumerate()
+            .filter(|(point_idx, _)| {
+                fixed.iter().all(|(idx, bit)| {
+                    ((point_idx >> (number_of_variables - 1 - idx)) & 1 == 1) == *bit
+                })
+            })
+            .map(|(_, point)| *point)
+            .sum()
+    }
+
     // Adds two polynomials of same variables together
     pub fn _add(&self, other: &MultiLinearPolynomial<T>) -> Self {
         start_tscope!("Add polynomial");
@@ -257,6 +560,29 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         Self::new(&new_evals)
     }
 
+    // Multiplies two polynomials of same variables together, evaluation point by evaluation point
+    // NOTE: the product of two multilinear polynomials is not multilinear (it is degree 2 in each variable),
+    // so the result only agrees with f(x) * g(x) on the boolean hypercube, not at arbitrary points.
+    // It is only meaningful where a product of factors is summed over the hypercube (e.g. ProductPolynomial::reduce)
+    pub fn hadamard_mul(&self, other: &MultiLinearPolynomial<T>) -> Self {
+        start_tscope!("Hadamard mul polynomial");
+
+        if self.number_of_variables() != other.number_of_variables() {
+            panic!("Polynomial must have the same length");
+        };
+
+        let new_evals = self
+            .evaluation_points
+            .iter()
+            .zip(&other.evaluation_points)
+            .map(|(a, b)| *a * b)
+            .collect::<Vec<_>>();
+
+        end_tscope!();
+
+        Self::new(&new_evals)
+    }
+
     // Performs F(x) - V operation
     pub fn minus(&self, other: &T) -> Self {
         start_tscope!("Minus polynomial");
@@ -272,6 +598,23 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
         Self::new(&new_evaluation_points)
     }
 
+    // The quotient half of compute_quotient_remainder, for when the remainder isn't needed
+    pub fn compute_quotient(&self, variable_index: usize) -> Vec<T> {
+        start_tscope!("Compute quotient");
+
+        let quotient = self
+            .get_y1_y2_indexes(variable_index)
+            .iter()
+            .map(|(y1_index, y2_index)| {
+                self.evaluation_points[*y2_index] - self.evaluation_points[*y1_index]
+            })
+            .collect::<Vec<_>>();
+
+        end_tscope!();
+
+        quotient
+    }
+
     // Dividing a polynomial at a variable point gives you the *quotient* and *remainder*
     // The quotient can be gotten by finding the two points at which the variable switches (from 0 to 1)
     //    - Then subtract the evaluation points at the indexes (y2 - y1)
@@ -280,20 +623,8 @@ impl<T: PrimeField> MultiLinearPolynomial<T> {
     pub fn compute_quotient_remainder(&self, divisor: &T, variable_index: usize) -> (Vec<T>, Self) {
         start_tscope!("Compute quotient remainder");
 
-        let y1_y2_indexes = self.get_y1_y2_indexes(variable_index);
-
         let remainder = self.partially_evaluate((variable_index, *divisor));
-        let quotient = y1_y2_indexes
-            .iter()
-            .map(|(y1_index, y2_index)| {
-                let (y1, y2) = (
-                    self.evaluation_points[*y1_index],
-                    self.evaluation_points[*y2_index],
-                );
-
-                y2 - y1
-            })
-            .collect::<Vec<_>>();
+        let quotient = self.compute_quotient(variable_index);
 
         end_tscope!();
 
@@ -320,6 +651,28 @@ impl<T: PrimeField> Add for MultiLinearPolynomial<T> {
     }
 }
 
+// Yields every point of {0, 1}^n in the same order as the evaluation points (first variable is the most significant bit)
+// E.g. for 2 variables: [false, false], [false, true], [true, false], [true, true]
+pub fn hypercube_points(n: usize) -> impl Iterator<Item = Vec<bool>> {
+    (0..1usize << n).map(move |idx| {
+        (0..n)
+            .map(|var_idx| (idx >> (n - 1 - var_idx)) & 1 == 1)
+            .collect()
+    })
+}
+
+// Builds eq(point, x) for every x in {0, 1}^n, i.e. the lagrange basis of the hypercube evaluated at point
+// so that the dot product with a polynomial's evaluation points is the polynomial evaluated at point
+// E.g. [a, b] => [(1 - a)(1 - b), (1 - a)b, a(1 - b), ab]
+pub fn eq_polynomial_evals<T: PrimeField>(point: &[T]) -> Vec<T> {
+    point.iter().fold(vec![T::one()], |evals, r| {
+        evals
+            .iter()
+            .flat_map(|eval| [*eval * (T::one() - r), *eval * r])
+            .collect()
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -418,6 +771,513 @@ mod test {
         print_summary!();
     }
 
+    // Reference partial evaluation straight from the definition: for every point of the remaining variables,
+    // put a 0 and then a 1 at the fixed variable's bit (variables are most significant bit first) and interpolate
+    fn brute_force_partially_evaluate(
+        evaluation_points: &[Fq],
+        variable_idx: usize,
+        r: Fq,
+    ) -> Vec<Fq> {
+        let number_of_variables = evaluation_points.len().ilog2() as usize;
+        let variable_bit = number_of_variables - 1 - variable_idx;
+
+        (0..evaluation_points.len() / 2)
+            .map(|point| {
+                let (high_bits, low_bits) =
+                    (point >> variable_bit, point & ((1 << variable_bit) - 1));
+                let y1_index = (high_bits << (variable_bit + 1)) | low_bits;
+                let y2_index = y1_index | (1 << variable_bit);
+
+                evaluation_points[y1_index]
+                    + (evaluation_points[y2_index] - evaluation_points[y1_index]) * r
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_partially_evaluate_every_variable() {
+        let r = Fq::from(7);
+
+        for number_of_variables in 1..=4 {
+            let evaluation_points = (0..1u64 << number_of_variables)
+                .map(|idx| Fq::from(idx * idx + 3 * idx + 1))
+                .collect::<Vec<_>>();
+            let mlp = MultiLinearPolynomial::new(&evaluation_points);
+
+            // the last variable pairs adjacent points, so its skip target is 1
+            for variable_idx in 0..number_of_variables {
+                assert_eq!(
+                    mlp.partially_evaluate((variable_idx, r)).evaluation_points,
+                    brute_force_partially_evaluate(&evaluation_points, variable_idx, r),
+                    "variable {} of {}",
+                    variable_idx,
+                    number_of_variables
+                );
+            }
+
+            assert_eq!(
+                mlp.partially_evaluate((number_of_variables - 1, r)),
+                mlp.fix_last_variable(r)
+            );
+        }
+
+        // 2ab + 3bc, fixing each of a, b, c in turn
+        let mlp = get_test_polynomial_2();
+
+        assert_eq!(
+            mlp.partially_evaluate((0, Fq::from(2))).evaluation_points,
+            vec![Fq::from(0), Fq::from(0), Fq::from(4), Fq::from(7)]
+        );
+        assert_eq!(
+            mlp.partially_evaluate((1, Fq::from(2))).evaluation_points,
+            vec![Fq::from(0), Fq::from(6), Fq::from(4), Fq::from(10)]
+        );
+        assert_eq!(
+            mlp.partially_evaluate((2, Fq::from(2))).evaluation_points,
+            vec![Fq::from(0), Fq::from(6), Fq::from(0), Fq::from(8)]
+        );
+
+        print_summary!();
+    }
+
+    // Reference multilinear extension straight from the definition:
+    // f(point) = sum over the hypercube of evals[x] * prod_i eq(point_i, x_i), with eq(p, 1) = p and eq(p, 0) = 1 - p
+    // Deliberately shares no code with the polynomial's own evaluation, so the two can be checked against each other
+    fn reference_evaluate<T: PrimeField>(evals: &[T], point: &[T]) -> T {
+        assert_eq!(evals.len(), 1 << point.len());
+
+        evals
+            .iter()
+            .enumerate()
+            .map(|(x, eval)| {
+                point
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        // the first variable is the most significant bit of x
+                        if (x >> (point.len() - 1 - i)) & 1 == 1 {
+                            *p
+                        } else {
+                            T::one() - p
+                        }
+                    })
+                    .product::<T>()
+                    * eval
+            })
+            .sum()
+    }
+
+    #[test]
+    pub fn test_reference_evaluate() {
+        // 2ab + 3bc at a = 1, b = 2, c = 3
+        assert_eq!(
+            reference_evaluate(
+                get_test_polynomial_2().get_evaluation_points(),
+                &[Fq::from(1), Fq::from(2), Fq::from(3)]
+            ),
+            Fq::from(22)
+        );
+
+        // on the hypercube it just picks out the evaluation
+        let evals = (1..=8).map(Fq::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            reference_evaluate(&evals, &[Fq::from(1), Fq::from(0), Fq::from(1)]),
+            Fq::from(6)
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_evaluate_matches_reference() {
+        use ark_bn254::Fr;
+        use ark_ff::UniformRand;
+
+        let mut rng = rand::thread_rng();
+
+        for number_of_variables in 0..=8 {
+            for _ in 0..5 {
+                let evals = (0..1 << number_of_variables)
+                    .map(|_| Fr::rand(&mut rng))
+                    .collect::<Vec<_>>();
+                let point = (0..number_of_variables)
+                    .map(|_| Fr::rand(&mut rng))
+                    .collect::<Vec<_>>();
+                let mlp = MultiLinearPolynomial::new(&evals);
+                let expected = reference_evaluate(&evals, &point);
+
+                let full_evaluation =
+                    mlp.evaluate(&point.iter().copied().map(Some).collect::<Vec<_>>());
+
+                assert_eq!(full_evaluation.evaluation_points, vec![expected]);
+
+                // fixing every other variable first and the rest afterwards lands on the same value
+                let (even_points, odd_points): (Vec<_>, Vec<_>) = (
+                    point
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| (i % 2 == 0).then_some(*p))
+                        .collect(),
+                    point
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| i % 2 == 1)
+                        .map(|(_, p)| Some(*p))
+                        .collect(),
+                );
+
+                assert_eq!(
+                    mlp.evaluate(&even_points)
+                        .evaluate(&odd_points)
+                        .evaluation_points,
+                    vec![expected]
+                );
+            }
+        }
+    }
+
+    // Checks the eq identity on random polynomials at random points over the given number of variables
+    fn check_eq_identity_on_random_inputs(number_of_variables: usize, trials: usize) {
+        use ark_bn254::Fr;
+        use ark_ff::UniformRand;
+
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..trials {
+            let mlp = MultiLinearPolynomial::new(
+                &(0..1 << number_of_variables)
+                    .map(|_| Fr::rand(&mut rng))
+                    .collect(),
+            );
+            let point = (0..number_of_variables)
+                .map(|_| Fr::rand(&mut rng))
+                .collect::<Vec<_>>();
+
+            assert!(
+                mlp.verify_eq_identity(&point),
+                "eq identity failed over {} variables",
+                number_of_variables
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_verify_eq_identity() {
+        for number_of_variables in 2..=6 {
+            check_eq_identity_on_random_inputs(number_of_variables, 10);
+        }
+
+        // 2ab + 3bc at (1, 2, 3), and a point with the wrong number of coordinates
+        let mlp = get_test_polynomial_2();
+
+        assert!(mlp.verify_eq_identity(&[Fq::from(1), Fq::from(2), Fq::from(3)]));
+        assert!(!mlp.verify_eq_identity(&[Fq::from(1), Fq::from(2)]));
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_evaluate_sparse_matches_positional_evaluate() {
+        // 3ac + 4bd + 5ab -> where b = 2, d = 1
+        let mlp = get_test_polynomial();
+
+        let assignment = BTreeMap::from([(1, Fq::from(2)), (3, Fq::from(1))]);
+
+        assert_eq!(
+            mlp.evaluate_sparse(&assignment),
+            mlp.evaluate(&[None, Some(Fq::from(2)), None, Some(Fq::from(1))])
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_sum_over_subcube() {
+        // 3ac + 4bd + 5ab
+        let mlp = get_test_polynomial();
+
+        // fixing nothing sums the whole hypercube
+        assert_eq!(mlp.sum_over_subcube(&[]), mlp.evaluation_sum());
+
+        // fixing everything picks a single point: a = 1, b = 0, c = 1, d = 1 => 3
+        assert_eq!(
+            mlp.sum_over_subcube(&[(0, true), (1, false), (2, true), (3, true)]),
+            mlp.evaluate(&[
+                Some(Fq::from(1)),
+                Some(Fq::from(0)),
+                Some(Fq::from(1)),
+                Some(Fq::from(1))
+            ])
+            .evaluation_points[0]
+        );
+
+        // a = 1, c = 0 => sum of 4bd + 5b over b, d => 0 + 0 + 5 + 9
+        assert_eq!(mlp.sum_over_subcube(&[(0, true), (2, false)]), Fq::from(14));
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_try_number_of_variables() {
+        assert_eq!(get_test_polynomial().try_number_of_variables(), Ok(4));
+
+        // bypass new, which would reject this length
+        let mlp = MultiLinearPolynomial {
+            evaluation_points: vec![Fq::from(1), Fq::from(2), Fq::from(3)],
+        };
+
+        assert_eq!(
+            mlp.try_number_of_variables(),
+            Err(MultiLinearPolynomialError::NonPowerOfTwoLength(3))
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "evaluation points length 3 is not a power of two")]
+    pub fn test_number_of_variables_non_power_of_two() {
+        let mlp = MultiLinearPolynomial {
+            evaluation_points: vec![Fq::from(1), Fq::from(2), Fq::from(3)],
+        };
+
+        mlp.number_of_variables();
+    }
+
+    #[test]
+    pub fn test_permute_variables() {
+        // 2 + 3a + 5b + 7ab
+        let mlp =
+            MultiLinearPolynomial::new(&vec![Fq::from(2), Fq::from(7), Fq::from(5), Fq::from(17)]);
+        let (a, b) = (Fq::from(4), Fq::from(9));
+
+        let permuted = mlp.permute_variables(&[1, 0]);
+
+        assert_eq!(
+            permuted.evaluate(&[Some(a), Some(b)]),
+            mlp.evaluate(&[Some(b), Some(a)])
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "perm must be a permutation of the variable indexes")]
+    pub fn test_permute_variables_rejects_non_permutation() {
+        get_test_polynomial_2().permute_variables(&[0, 0, 1]);
+    }
+
+    #[test]
+    pub fn test_fix_last_variable() {
+        // 2ab + 3bc
+        let mlp = get_test_polynomial_2();
+
+        assert_eq!(
+            mlp.fix_last_variable(Fq::from(3)),
+            mlp.partially_evaluate((2, Fq::from(3)))
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_eq_polynomial_evals() {
+        // 3ac + 4bd + 5ab -> where a = 4, b = 2, c = 6, d = 1
+        let mlp = get_test_polynomial();
+        let point = [Fq::from(4), Fq::from(2), Fq::from(6), Fq::from(1)];
+
+        let dot_product: Fq = eq_polynomial_evals(&point)
+            .iter()
+            .zip(mlp.get_evaluation_points())
+            .map(|(eq_eval, eval)| *eq_eval * eval)
+            .sum();
+
+        assert_eq!(
+            dot_product,
+            mlp.evaluate(&point.map(Some)).get_evaluation_points()[0]
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_hypercube_points() {
+        let points = hypercube_points(3).collect::<Vec<_>>();
+
+        assert_eq!(points.len(), 8);
+        assert_eq!(points[0], vec![false, false, false]);
+        assert_eq!(points[1], vec![false, false, true]);
+        assert_eq!(points[6], vec![true, true, false]);
+        assert_eq!(points[7], vec![true, true, true]);
+    }
+
+    #[test]
+    pub fn test_from_fn() {
+        // a AND b
+        let mlp = MultiLinearPolynomial::from_fn(2, |point| {
+            if point[0] && point[1] {
+                Fq::from(1)
+            } else {
+                Fq::from(0)
+            }
+        });
+
+        assert_eq!(
+            mlp,
+            MultiLinearPolynomial::new(&vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(1)])
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_rotate() {
+        let mlp = get_test_polynomial_2();
+        let rotated = mlp.rotate(1);
+
+        let evaluate_at = |poly: &MultiLinearPolynomial<Fq>, point: &[bool]| {
+            poly.evaluate(
+                &point
+                    .iter()
+                    .map(|bit| Some(Fq::from(*bit as u64)))
+                    .collect::<Vec<_>>(),
+            )
+            .get_evaluation_points()[0]
+        };
+
+        hypercube_points(3).enumerate().for_each(|(idx, point)| {
+            let shifted_point = hypercube_points(3).nth((idx + 1) % 8).unwrap();
+
+            assert_eq!(
+                evaluate_at(&rotated, &point),
+                evaluate_at(&mlp, &shifted_point)
+            );
+        });
+
+        // a full cycle gives back the original
+        assert_eq!(mlp.rotate(8), mlp);
+        assert_eq!(mlp.rotate(3).rotate(5), mlp);
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_zero() {
+        let zero = MultiLinearPolynomial::<Fq>::zero(3);
+
+        assert_eq!(zero.number_of_variables(), 3);
+        assert!(zero
+            .get_evaluation_points()
+            .iter()
+            .all(|eval| *eval == Fq::from(0)));
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_from_hypercube_pairs() {
+        // a + b
+        let mlp = MultiLinearPolynomial::from_hypercube_pairs(
+            2,
+            &[
+                (vec![false, false], Fq::from(0)),
+                (vec![true, true], Fq::from(2)),
+                (vec![false, true], Fq::from(1)),
+                (vec![true, false], Fq::from(1)),
+            ],
+        );
+
+        assert_eq!(
+            mlp,
+            MultiLinearPolynomial::new(&vec![Fq::from(0), Fq::from(1), Fq::from(1), Fq::from(2)])
+        );
+
+        // unspecified points are zero
+        let sparse_mlp = MultiLinearPolynomial::from_hypercube_pairs(
+            3,
+            &[(vec![true, false, true], Fq::from(7))],
+        );
+        let mut expected = vec![Fq::from(0); 8];
+        expected[5] = Fq::from(7);
+
+        assert_eq!(*sparse_mlp.get_evaluation_points(), expected);
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "point has 1 coordinates, expected 2")]
+    pub fn test_from_hypercube_pairs_with_wrong_point_length() {
+        MultiLinearPolynomial::from_hypercube_pairs(2, &[(vec![true], Fq::from(1))]);
+    }
+
+    #[test]
+    pub fn test_extend_from_values() {
+        let values = vec![Fq::from(3), Fq::from(5), Fq::from(7)];
+        let (mlp, original_length) = MultiLinearPolynomial::extend_from_values(&values);
+
+        assert_eq!(mlp.number_of_variables(), 2);
+        assert_eq!(original_length, 3);
+        assert_eq!(mlp.get_evaluation_points()[..original_length], values);
+        assert_eq!(mlp.get_evaluation_points()[3], Fq::from(0));
+
+        // power of two lengths are left as they are
+        let (mlp, original_length) =
+            MultiLinearPolynomial::extend_from_values(&[Fq::from(1), Fq::from(2)]);
+
+        assert_eq!(
+            mlp,
+            MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(2)])
+        );
+        assert_eq!(original_length, 2);
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_extend_variable() {
+        let (low_branch, high_branch) = (
+            get_test_polynomial_2(),
+            get_test_polynomial_2().scalar_mul(Fq::from(3)),
+        );
+
+        let extended = low_branch.extend_variable(&high_branch);
+
+        assert_eq!(extended.number_of_variables(), 4);
+        assert_eq!(extended.partially_evaluate((0, Fq::from(0))), low_branch);
+        assert_eq!(extended.partially_evaluate((0, Fq::from(1))), high_branch);
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_split_first_variable() {
+        // 2ab + 3bc
+        let mlp = get_test_polynomial_2();
+        let r = Fq::from(7);
+
+        let (low_branch, high_branch) = mlp.split_first_variable();
+
+        assert_eq!(low_branch.number_of_variables(), 2);
+        assert_eq!(high_branch.number_of_variables(), 2);
+
+        // f(r, b, c) = low + (high - low) * r
+        let expected = low_branch
+            .get_evaluation_points()
+            .iter()
+            .zip(high_branch.get_evaluation_points())
+            .map(|(low, high)| *low + (*high - *low) * r)
+            .collect();
+
+        assert_eq!(
+            mlp.partially_evaluate((0, r)),
+            MultiLinearPolynomial::new(&expected)
+        );
+
+        print_summary!();
+    }
+
     #[test]
     pub fn test_operation_ws() {
         let (w_b, w_c) = (
@@ -506,4 +1366,152 @@ mod test {
 
         print_summary!();
     }
+
+    #[test]
+    pub fn test_linear_combination() {
+        let polys = vec![
+            MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(2), Fq::from(3), Fq::from(4)]),
+            MultiLinearPolynomial::new(&vec![Fq::from(0), Fq::from(5), Fq::from(0), Fq::from(5)]),
+            MultiLinearPolynomial::new(&vec![Fq::from(7), Fq::from(1), Fq::from(9), Fq::from(2)]),
+        ];
+        let coeffs = vec![Fq::from(3), Fq::from(2), Fq::from(6)];
+
+        let expected = polys[0].scalar_mul(coeffs[0])
+            + polys[1].scalar_mul(coeffs[1])
+            + polys[2].scalar_mul(coeffs[2]);
+
+        assert_eq!(
+            MultiLinearPolynomial::linear_combination(&polys, &coeffs),
+            expected
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "must have the same number of variables")]
+    pub fn test_linear_combination_with_mismatched_variables() {
+        MultiLinearPolynomial::linear_combination(
+            &[
+                MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(2)]),
+                MultiLinearPolynomial::new(&vec![
+                    Fq::from(1),
+                    Fq::from(2),
+                    Fq::from(3),
+                    Fq::from(4),
+                ]),
+            ],
+            &[Fq::from(1), Fq::from(1)],
+        );
+    }
+
+    #[test]
+    pub fn test_into_evaluation_points() {
+        let mlp = get_test_polynomial();
+        let borrowed_evaluation_points = mlp.get_evaluation_points().clone();
+
+        assert_eq!(mlp.into_evaluation_points(), borrowed_evaluation_points);
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_eq_extension() {
+        // 2ab + 3bc -> where a = 3, b = 7, c = 5
+        let mlp = get_test_polynomial_2();
+        let point = [Fq::from(3), Fq::from(7), Fq::from(5)];
+
+        let eq_ext = MultiLinearPolynomial::eq_extension(&point);
+
+        assert_eq!(eq_ext.number_of_variables(), 3);
+
+        let dot_product: Fq = eq_ext
+            .get_evaluation_points()
+            .iter()
+            .zip(mlp.get_evaluation_points())
+            .map(|(eq_eval, eval)| *eq_eval * eval)
+            .sum();
+
+        assert_eq!(
+            dot_product,
+            mlp.evaluate(&point.map(Some)).get_evaluation_points()[0]
+        );
+
+        // on the hypercube, eq(point, x) is the indicator of x == point
+        let boolean_point = [Fq::from(1), Fq::from(0), Fq::from(1)];
+        let mut indicator = vec![Fq::from(0); 8];
+        indicator[5] = Fq::from(1);
+
+        assert_eq!(
+            MultiLinearPolynomial::eq_extension(&boolean_point).get_evaluation_points(),
+            &indicator
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_hadamard_mul() {
+        use crate::product_polynomial::ProductPolynomial;
+
+        let mlp = get_test_polynomial_2();
+        let other = MultiLinearPolynomial::new(&(1..=8u64).map(Fq::from).collect::<Vec<_>>());
+
+        let product = mlp.hadamard_mul(&other);
+
+        assert_eq!(
+            *product.get_evaluation_points(),
+            vec![
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(12),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(14),
+                Fq::from(40),
+            ]
+        );
+        assert_eq!(
+            *product.get_evaluation_points(),
+            ProductPolynomial::new(vec![mlp, other]).reduce()
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "Polynomial must have the same length")]
+    pub fn test_hadamard_mul_with_mismatched_variables() {
+        get_test_polynomial().hadamard_mul(&get_test_polynomial_2());
+    }
+
+    #[test]
+    pub fn test_restrict_to_line() {
+        use ark_ff::Field;
+
+        // 3a + 4b + 2ab
+        let mlp =
+            MultiLinearPolynomial::new(&vec![Fq::from(0), Fq::from(4), Fq::from(3), Fq::from(9)]);
+        let (p0, p1) = ([Fq::from(2), Fq::from(5)], [Fq::from(7), Fq::from(3)]);
+
+        let line = mlp.restrict_to_line(&p0, &p1);
+        let evaluate_at = |point: &[Fq]| {
+            mlp.evaluate(&point.iter().map(|x| Some(*x)).collect::<Vec<_>>())
+                .get_evaluation_points()[0]
+        };
+
+        assert_eq!(line.evaluate(Fq::from(0)), evaluate_at(&p0));
+        assert_eq!(line.evaluate(Fq::from(1)), evaluate_at(&p1));
+
+        // t = 1/2 is the midpoint of p0 and p1
+        let half = Fq::from(2).inverse().unwrap();
+
+        assert_eq!(
+            line.evaluate(half),
+            evaluate_at(&[(p0[0] + p1[0]) * half, (p0[1] + p1[1]) * half])
+        );
+
+        print_summary!();
+    }
 }
@@ -3,6 +3,12 @@ use crate::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
 use ark_ff::PrimeField;
 use std::iter;
 
+#[derive(Debug, PartialEq)]
+pub enum PolyError {
+    // The number of evaluation points doesn't match the number of variables of the polynomial
+    VariableCountMismatch { expected: usize, got: usize },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ProductPolynomial<T: PrimeField> {
     pub polys: Vec<MultiLinearPolynomial<T>>,
@@ -51,10 +57,34 @@ impl<T: PrimeField> ProductPolynomial<T> {
             .product()
     }
 
+    // Same as evaluate, but returns an error instead of panicking on a wrong number of evaluation points
+    pub fn try_evaluate(&self, t: &[Option<T>]) -> Result<T, PolyError> {
+        let number_of_variables = self.length().ilog2() as usize;
+
+        if t.len() != number_of_variables {
+            return Err(PolyError::VariableCountMismatch {
+                expected: number_of_variables,
+                got: t.len(),
+            });
+        }
+
+        Ok(self.evaluate(t))
+    }
+
     pub fn reduce(&self) -> Vec<T> {
         // perform element wise product on each multilinear polynomial
         let general_poly_length = Self::get_poly_length(&self.polys);
 
+        // guard against factors that drifted apart in length (e.g. after a faulty partial evaluation)
+        self.polys.iter().for_each(|poly| {
+            debug_assert!(
+                poly.get_evaluation_points().len() == general_poly_length,
+                "Mismatched polynomial lengths in reduce: expected {}, got {}",
+                general_poly_length,
+                poly.get_evaluation_points().len()
+            );
+        });
+
         let res = iter::repeat(())
             .enumerate()
             .map(|(index, _)| {
@@ -75,6 +105,11 @@ impl<T: PrimeField> ProductPolynomial<T> {
     pub fn to_bytes(&self) -> Vec<u8> {
         self.polys.iter().flat_map(|poly| poly.to_bytes()).collect()
     }
+
+    // The degree of the product in each of its variables: every multilinear factor contributes degree 1
+    pub fn per_variable_degree(&self) -> usize {
+        self.polys.len()
+    }
 }
 
 #[cfg(test)]
@@ -101,6 +136,37 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Mismatched polynomial lengths in reduce: expected 4, got 2")]
+    fn test_product_polynomial_reduce_mismatched_lengths() {
+        let malformed_poly = ProductPolynomial {
+            polys: vec![
+                MultiLinearPolynomial::new(&vec![
+                    Fq::from(1),
+                    Fq::from(2),
+                    Fq::from(3),
+                    Fq::from(4),
+                ]),
+                MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(2)]),
+            ],
+        };
+
+        malformed_poly.reduce();
+    }
+
+    #[test]
+    fn test_product_polynomial_per_variable_degree() {
+        let evals = vec![Fq::from(1), Fq::from(2), Fq::from(3), Fq::from(4)];
+
+        assert_eq!(get_test_product_polynomial().per_variable_degree(), 2);
+        assert_eq!(
+            ProductPolynomial::new(vec![MultiLinearPolynomial::new(&evals); 3])
+                .per_variable_degree(),
+            3
+        );
+    }
+
     #[test]
     fn test_product_polynomial_evaluate() {
         let test_poly = get_test_product_polynomial();
@@ -111,6 +177,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_product_polynomial_try_evaluate() {
+        let test_poly = get_test_product_polynomial();
+
+        assert_eq!(
+            test_poly.try_evaluate(&[Some(Fq::from(1)), Some(Fq::from(2))]),
+            Ok(Fq::from(25))
+        );
+        assert_eq!(
+            test_poly.try_evaluate(&[Some(Fq::from(1))]),
+            Err(PolyError::VariableCountMismatch {
+                expected: 2,
+                got: 1
+            })
+        );
+    }
+
     #[test]
     fn test_product_polynomial_partial_evaluate() {
         let test_poly = get_test_product_polynomial();
@@ -1,4 +1,4 @@
-use crate::product_polynomial::ProductPolynomial;
+use crate::product_polynomial::{PolyError, ProductPolynomial};
 
 use ark_ff::PrimeField;
 use std::iter;
@@ -34,6 +34,23 @@ impl<T: PrimeField> SumPolynomial<T> {
         self.prod_polys.iter().map(|poly| poly.evaluate(t)).sum()
     }
 
+    // Evaluates each product polynomial separately, in order, without summing them
+    // Useful for seeing which term diverged when a check on the sum fails (e.g. the add_i or the mul_i term in GKR)
+    pub fn evaluate_terms(&self, t: &[Option<T>]) -> Vec<T> {
+        self.prod_polys
+            .iter()
+            .map(|poly| poly.evaluate(t))
+            .collect()
+    }
+
+    // Same as evaluate, but returns an error instead of panicking on a wrong number of evaluation points
+    pub fn try_evaluate(&self, t: &[Option<T>]) -> Result<T, PolyError> {
+        self.prod_polys
+            .iter()
+            .map(|poly| poly.try_evaluate(t))
+            .sum()
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         self.prod_polys
             .iter()
@@ -44,6 +61,16 @@ impl<T: PrimeField> SumPolynomial<T> {
     pub fn reduce(&self) -> Vec<T> {
         // perform element wise product on each multilinear polynomial
         let general_poly_length = self.length();
+
+        // guard against product polynomials that drifted apart in length (e.g. after a faulty partial evaluation)
+        self.prod_polys.iter().for_each(|poly| {
+            debug_assert!(
+                poly.length() == general_poly_length,
+                "Mismatched polynomial lengths in reduce: expected {}, got {}",
+                general_poly_length,
+                poly.length()
+            );
+        });
         let reduced_product_polys: Vec<Vec<T>> =
             self.prod_polys.iter().map(|poly| poly.reduce()).collect();
 
@@ -64,6 +91,41 @@ impl<T: PrimeField> SumPolynomial<T> {
         res
     }
 
+    // Sums the polynomial over the hypercube of the remaining variables with the first variable fixed at 0, 1, ..., per_variable_degree.
+    // This gives the same values as partially evaluating the first variable at each point, then reducing and summing,
+    // but in a single pass without building the partially evaluated polynomials.
+    // Each factor is linear in the first variable, so f(x, ..) = low + x * (high - low)
+    pub fn first_variable_sums(&self) -> Vec<T> {
+        let number_of_points = self.per_variable_degree() + 1;
+        let half_length = self.length() / 2;
+        let mut sums = vec![T::zero(); number_of_points];
+
+        for prod_poly in &self.prod_polys {
+            for idx in 0..half_length {
+                let mut products = vec![T::one(); number_of_points];
+
+                for poly in &prod_poly.polys {
+                    let evaluation_points = poly.get_evaluation_points();
+                    let (low, high) =
+                        (evaluation_points[idx], evaluation_points[idx + half_length]);
+
+                    let mut evaluation_at_x = low;
+
+                    for product in products.iter_mut() {
+                        *product *= evaluation_at_x;
+                        evaluation_at_x += high - low;
+                    }
+                }
+
+                sums.iter_mut()
+                    .zip(products)
+                    .for_each(|(sum, product)| *sum += product);
+            }
+        }
+
+        sums
+    }
+
     pub fn get_poly_length(prod_polys: &[ProductPolynomial<T>]) -> usize {
         prod_polys.first().unwrap().length()
     }
@@ -76,9 +138,28 @@ impl<T: PrimeField> SumPolynomial<T> {
         self.length().ilog2()
     }
 
+    // Kept for existing callers: despite the name this is the number of product terms, not a degree
+    #[deprecated(
+        note = "returns the number of product terms, use num_terms (or per_variable_degree for the degree)"
+    )]
     pub fn degree(&self) -> usize {
+        self.num_terms()
+    }
+
+    // The number of product terms being summed (see per_variable_degree for the degree)
+    pub fn num_terms(&self) -> usize {
         self.prod_polys.len()
     }
+
+    // The degree of the polynomial in each of its variables, i.e. that of its highest degree product
+    // A sumcheck round polynomial has this degree, so it takes this many points plus one to interpolate
+    pub fn per_variable_degree(&self) -> usize {
+        self.prod_polys
+            .iter()
+            .map(|prod_poly| prod_poly.per_variable_degree())
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +194,25 @@ mod tests {
         print_summary!();
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Mismatched polynomial lengths in reduce: expected 4, got 2")]
+    fn test_sum_polynomial_reduce_mismatched_lengths() {
+        let malformed_poly = SumPolynomial {
+            prod_polys: vec![
+                get_test_prod_polynomial(),
+                ProductPolynomial::new(vec![MultiLinearPolynomial::new(&vec![
+                    Fq::from(2),
+                    Fq::from(3),
+                ])]),
+            ],
+        };
+
+        malformed_poly.reduce();
+
+        print_summary!();
+    }
+
     #[test]
     fn test_sum_polynomial_evaluate() {
         let test_poly = get_test_sum_polynomial();
@@ -125,6 +225,125 @@ mod tests {
         print_summary!();
     }
 
+    #[test]
+    fn test_sum_polynomial_try_evaluate() {
+        let test_poly = get_test_sum_polynomial();
+
+        assert_eq!(
+            test_poly.try_evaluate(&[Some(Fq::from(1)), Some(Fq::from(2))]),
+            Ok(Fq::from(72))
+        );
+        assert_eq!(
+            test_poly.try_evaluate(&[Some(Fq::from(1)), Some(Fq::from(2)), None]),
+            Err(PolyError::VariableCountMismatch {
+                expected: 2,
+                got: 3
+            })
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_sum_polynomial_evaluate_terms() {
+        // add_i * (w_b + w_c) + mul_i * (w_b * w_c) shaped polynomial over 2 variables
+        let test_poly = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&vec![
+                    Fq::from(1),
+                    Fq::from(0),
+                    Fq::from(0),
+                    Fq::from(1),
+                ]),
+                MultiLinearPolynomial::new(&vec![
+                    Fq::from(3),
+                    Fq::from(5),
+                    Fq::from(7),
+                    Fq::from(9),
+                ]),
+            ]),
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&vec![
+                    Fq::from(0),
+                    Fq::from(1),
+                    Fq::from(1),
+                    Fq::from(0),
+                ]),
+                MultiLinearPolynomial::new(&vec![
+                    Fq::from(2),
+                    Fq::from(4),
+                    Fq::from(6),
+                    Fq::from(8),
+                ]),
+            ]),
+        ]);
+
+        // only the add term is on at (1, 1), only the mul term at (0, 1)
+        assert_eq!(
+            test_poly.evaluate_terms(&[Some(Fq::from(1)), Some(Fq::from(1))]),
+            vec![Fq::from(9), Fq::from(0)]
+        );
+        assert_eq!(
+            test_poly.evaluate_terms(&[Some(Fq::from(0)), Some(Fq::from(1))]),
+            vec![Fq::from(0), Fq::from(4)]
+        );
+
+        let point = [Some(Fq::from(2)), Some(Fq::from(3))];
+
+        assert_eq!(
+            test_poly.evaluate_terms(&point).iter().copied().sum::<Fq>(),
+            test_poly.evaluate(&point)
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_sum_polynomial_per_variable_degree() {
+        let evals = vec![Fq::from(1), Fq::from(2), Fq::from(3), Fq::from(4)];
+        let three_factor_product =
+            ProductPolynomial::new(vec![MultiLinearPolynomial::new(&evals); 3]);
+
+        assert_eq!(get_test_sum_polynomial().per_variable_degree(), 2);
+
+        // the highest degree product sets the degree, not the number of products
+        let test_poly = SumPolynomial::new(vec![get_test_prod_polynomial(), three_factor_product]);
+
+        assert_eq!(test_poly.per_variable_degree(), 3);
+        assert_eq!(test_poly.first_variable_sums().len(), 4);
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_first_variable_sums() {
+        // add_i * (w_b + w_c) + mul_i * (w_b * w_c) shaped polynomial over 3 variables
+        let (add_i, mul_i, w_b_plus_w_c, w_b_mul_w_c) = (
+            MultiLinearPolynomial::new(&(1..=8).map(Fq::from).collect()),
+            MultiLinearPolynomial::new(&(9..=16).map(Fq::from).collect()),
+            MultiLinearPolynomial::new(&(3..=10).map(|i| Fq::from(i * 2)).collect()),
+            MultiLinearPolynomial::new(&(5..=12).map(|i| Fq::from(i * i)).collect()),
+        );
+
+        let test_poly = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![add_i, w_b_plus_w_c]),
+            ProductPolynomial::new(vec![mul_i, w_b_mul_w_c]),
+        ]);
+
+        let expected = (0..test_poly.per_variable_degree() + 1)
+            .map(|i| {
+                let mut points = vec![None; test_poly.number_of_variables() as usize];
+                points[0] = Some(Fq::from(i as u8));
+
+                test_poly.partial_evaluate(&points).reduce().iter().sum()
+            })
+            .collect::<Vec<Fq>>();
+
+        assert_eq!(test_poly.first_variable_sums(), expected);
+
+        print_summary!();
+    }
+
     #[test]
     fn test_sum_polynomial_partial_evaluate() {
         let test_poly = get_test_sum_polynomial();
@@ -1,9 +1,13 @@
-use ark_ff::{BigInteger, PrimeField};
+use ark_ec::pairing::Pairing;
+use ark_ec::PrimeGroup;
+use ark_ff::{BigInteger, FftField, PrimeField};
+use fft::fft::Polynomial as FFT;
 use field_tracker::{end_tscope, start_tscope};
+use std::collections::BTreeMap;
 use std::ops::{Add, Mul};
 use std::{cmp, mem};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct UnivariatePolynomial<T: PrimeField> {
     pub coefficients: Vec<T>,
 }
@@ -33,6 +37,16 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
         result
     }
 
+    // Evaluate the polynomial at each of the given points
+    pub fn evaluate_batch(&self, points: &[T]) -> Vec<T> {
+        points.iter().map(|point| self.evaluate(*point)).collect()
+    }
+
+    // Evaluations of the polynomial over an explicit domain, in the domain's order
+    pub fn to_evaluations(&self, domain: &[T]) -> Vec<T> {
+        self.evaluate_batch(domain)
+    }
+
     // Get evaluation of the polynomial over the boolean hypercube and return sum
     pub fn evaluate_sum_over_boolean_hypercube(&self) -> T {
         start_tscope!("Univariate Polynomial Sum Over Boolean HC");
@@ -44,12 +58,28 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
         sum
     }
 
+    // Interpolates the evaluations at the implicit domain 0, 1, 2, ..., i.e. evals[i] = f(i)
+    // This is the inverse of sampling a sumcheck round polynomial at integer points
+    pub fn from_hypercube_evaluations(evals: &[T]) -> Self {
+        let x_points = (0..evals.len() as u64).map(T::from).collect::<Vec<_>>();
+
+        Self::interpolate(&x_points, evals)
+    }
+
     // Given a specific list of points, find the original polynomial
     // To do this, we use perform Lagrange interpolation on the points:
     //           (x - x1)(x - x2)...(x - xn)                  (x - x0)(x - x2)...(x - xn)                   (x - x0)(x - x1)...(x - xn-1)
     // f(x) =   ------------------------------   * y0   +  ------------------------------   * y1 ..... +  ------------------------------  * yn
     //          (x0 - x1)(x0 - x2)...(x0 - xn)              (x1 - x0)(x1 - x2)...(x1 - xn)                (xn - x0)(xn - x1)...(xn - xn-1)
     pub fn interpolate(x_points: &[T], y_points: &[T]) -> Self {
+        // a repeated x point would make a denominator zero
+        if let Some((first, second)) = has_duplicates(x_points) {
+            panic!(
+                "cannot interpolate: x points at index {} and {} are the same",
+                first, second
+            );
+        }
+
         start_tscope!("Univariate Interpolate");
 
         let n = x_points.len();
@@ -71,12 +101,8 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
                     continue;
                 }
 
-                let int_poly = UnivariatePolynomial {
-                    coefficients: vec![-T::from(x_points[j]), T::from(1)],
-                };
-
                 denominator *= T::from(x_points[i]) - T::from(x_points[j]);
-                numerator = numerator * int_poly
+                numerator = numerator.mul_linear(x_points[j]);
             }
 
             res = res + (numerator.scalar_mul(y_points[i] / denominator));
@@ -87,6 +113,17 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
         res
     }
 
+    // Check the polynomial against a list of points and return the first one it doesn't pass through
+    // The result is (index, expected y, actual evaluation). This is meant for debugging interpolation.
+    pub fn find_failing_point(&self, x_points: &[T], y_points: &[T]) -> Option<(usize, T, T)> {
+        x_points
+            .iter()
+            .zip(y_points.iter())
+            .enumerate()
+            .map(|(idx, (x, y))| (idx, *y, self.evaluate(*x)))
+            .find(|(_, expected, actual)| expected != actual)
+    }
+
     // perform scalar mul between number and polynomial. Alternatively, you could represent a constant number as a polynomial i.e
     // UnivariatePolynomial {
     //      coefficients: [1]
@@ -106,6 +143,23 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
         UnivariatePolynomial { coefficients: res }
     }
 
+    // Multiply the polynomial by (x - a)
+    // Same as multiplying by UnivariatePolynomial::new(vec![-a, 1]), but in O(n): every coefficient
+    // moves up a degree (the x part) and -a times it stays at its degree (the -a part)
+    pub fn mul_linear(&self, a: T) -> Self {
+        let mut coefficients = vec![T::from(0); self.coefficients.len() + 1];
+
+        self.coefficients
+            .iter()
+            .enumerate()
+            .for_each(|(idx, coefficient)| {
+                coefficients[idx + 1] += coefficient;
+                coefficients[idx] -= a * coefficient;
+            });
+
+        UnivariatePolynomial { coefficients }
+    }
+
     // Multiply polynomials together
     // You get a polynomial with a degree of the highest degrees in each polynomial multiplied together
     pub fn _mul(&self, p2: &Self) -> Self {
@@ -179,6 +233,59 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
         }
     }
 
+    // Returns p(x + c)
+    // Horner's rule with x + c in place of x: starting from the leading coefficient,
+    // repeatedly multiply the running polynomial by (x + c) and add the next coefficient
+    pub fn shift(&self, c: T) -> Self {
+        let mut coefficients: Vec<T> = Vec::with_capacity(self.coefficients.len());
+
+        for coefficient in self.coefficients.iter().rev() {
+            // (x + c) * running: each coefficient picks up the one below it, plus c times itself
+            coefficients.push(T::zero());
+            for idx in (1..coefficients.len()).rev() {
+                coefficients[idx] = coefficients[idx - 1] + c * coefficients[idx];
+            }
+            coefficients[0] = c * coefficients[0] + coefficient;
+        }
+
+        UnivariatePolynomial { coefficients }
+    }
+
+    // Appends zero coefficients up to len, e.g. to reach a power of two length before an FFT
+    // The higher coefficients are zero, so the evaluations don't change
+    pub fn pad_to(&self, len: usize) -> Self {
+        if len < self.coefficients.len() {
+            panic!(
+                "cannot pad a polynomial with {} coefficients to length {}",
+                self.coefficients.len(),
+                len
+            );
+        }
+
+        let mut coefficients = self.coefficients.clone();
+        coefficients.resize(len, T::zero());
+
+        UnivariatePolynomial { coefficients }
+    }
+
+    // KZG commitment to the polynomial: ∑ c_i * g^(tau^i), given the encrypted powers g^(tau^0), g^(tau^1), ...
+    // The univariate analogue of committing a multilinear polynomial against the encrypted lagrange basis
+    pub fn commit<P: Pairing<ScalarField = T>>(&self, encrypted_powers: &[P::G1]) -> P::G1 {
+        if encrypted_powers.len() < self.coefficients.len() {
+            panic!(
+                "{} encrypted powers can't commit to a polynomial with {} coefficients",
+                encrypted_powers.len(),
+                self.coefficients.len()
+            );
+        }
+
+        self.coefficients
+            .iter()
+            .zip(encrypted_powers)
+            .map(|(coefficient, power)| power.mul_bigint(coefficient.into_bigint()))
+            .sum()
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         // Convert evaluation points to a serializable format (e.g., bytes)
         let serializable_points: Vec<u8> = self
@@ -191,6 +298,43 @@ impl<T: PrimeField> UnivariatePolynomial<T> {
     }
 }
 
+impl<T: PrimeField + FftField> UnivariatePolynomial<T> {
+    // Evaluations over the multiplicative subgroup of the given size, i.e. at w^0, w^1, ..., w^(size - 1)
+    // Same as to_evaluations on the roots of unity, but done with an FFT
+    // size must be a power of two the field has a root of unity for
+    pub fn to_evaluations_subgroup(&self, size: usize) -> Vec<T> {
+        if !size.is_power_of_two() || T::get_root_of_unity(size as u64).is_none() {
+            panic!("no multiplicative subgroup of size {}", size);
+        }
+
+        // w^size = 1, so coefficients past the subgroup size wrap around onto the lower powers
+        let mut coefficients = vec![T::zero(); size];
+
+        self.coefficients
+            .iter()
+            .enumerate()
+            .for_each(|(idx, coefficient)| coefficients[idx % size] += coefficient);
+
+        FFT::convert_to_evaluations(&coefficients)
+    }
+}
+
+// Returns the indexes of the first pair of equal points (by the later index), if any
+// Interpolating through points with a repeated x divides by zero, so callers check this first
+pub fn has_duplicates<T: PrimeField>(xs: &[T]) -> Option<(usize, usize)> {
+    let mut seen = BTreeMap::new();
+
+    xs.iter()
+        .enumerate()
+        .find_map(|(idx, x)| match seen.get(x) {
+            Some(first_idx) => Some((*first_idx, idx)),
+            None => {
+                seen.insert(*x, idx);
+                None
+            }
+        })
+}
+
 impl<T: PrimeField> Add for UnivariatePolynomial<T> {
     type Output = Self;
 
@@ -243,6 +387,92 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_commit() {
+        use ark_bn254::{Bn254, Fr, G1Projective};
+        use ark_ff::Field;
+
+        // 4x^2 + 3x + 2 against the powers of tau = 5
+        let tau = Fr::from(5);
+        let poly = UnivariatePolynomial::new(vec![Fr::from(2), Fr::from(3), Fr::from(4)]);
+        let encrypted_powers = (0..4u64)
+            .map(|power| G1Projective::generator().mul_bigint(tau.pow([power]).into_bigint()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            poly.commit::<Bn254>(&encrypted_powers),
+            G1Projective::generator().mul_bigint(Fr::from(117).into_bigint())
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "2 encrypted powers can't commit to a polynomial with 3 coefficients"
+    )]
+    pub fn test_commit_with_too_few_powers() {
+        use ark_bn254::{Bn254, Fr, G1Projective};
+
+        let _ = UnivariatePolynomial::new(vec![Fr::from(2), Fr::from(3), Fr::from(4)])
+            .commit::<Bn254>(&[G1Projective::generator(); 2]);
+    }
+
+    #[test]
+    pub fn test_mul_linear() {
+        use ark_bn254::Fr;
+        use ark_ff::UniformRand;
+
+        let poly =
+            UnivariatePolynomial::new(vec![Fr::from(7), Fr::from(0), Fr::from(3), Fr::from(11)]);
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..5 {
+            let a = Fr::rand(&mut rng);
+
+            assert_eq!(
+                poly.mul_linear(a).coefficients,
+                poly._mul(&UnivariatePolynomial::new(vec![-a, Fr::from(1)]))
+                    .coefficients
+            );
+        }
+
+        // (x + 2)(x - 3) = x^2 - x - 6
+        assert_eq!(
+            UnivariatePolynomial::new(vec![Fr::from(2), Fr::from(1)])
+                .mul_linear(Fr::from(3))
+                .coefficients,
+            vec![-Fr::from(6), -Fr::from(1), Fr::from(1)]
+        );
+    }
+
+    #[test]
+    pub fn test_has_duplicates() {
+        assert_eq!(
+            has_duplicates(&[Fq::from(1), Fq::from(2), Fq::from(3)]),
+            None
+        );
+        assert_eq!(
+            has_duplicates(&[
+                Fq::from(1),
+                Fq::from(5),
+                Fq::from(3),
+                Fq::from(5),
+                Fq::from(1)
+            ]),
+            Some((1, 3))
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot interpolate: x points at index 0 and 2 are the same")]
+    pub fn test_interpolate_with_duplicate_x_points() {
+        UnivariatePolynomial::interpolate(
+            &[Fq::from(4), Fq::from(1), Fq::from(4)],
+            &[Fq::from(2), Fq::from(3), Fq::from(5)],
+        );
+    }
+
     #[test]
     pub fn test_fibonacci_range() {
         let poly = UnivariatePolynomial::interpolate(
@@ -308,4 +538,132 @@ mod test {
 
         print_summary!();
     }
+
+    #[test]
+    pub fn test_from_hypercube_evaluations() {
+        // 3x^2 + 2x + 7
+        let poly = UnivariatePolynomial::new(vec![Fq::from(7), Fq::from(2), Fq::from(3)]);
+
+        let evals = (0..3u64)
+            .map(|x| poly.evaluate(Fq::from(x)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(evals, vec![Fq::from(7), Fq::from(12), Fq::from(23)]);
+        assert_eq!(
+            UnivariatePolynomial::from_hypercube_evaluations(&evals).coefficients,
+            poly.coefficients
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_find_failing_point() {
+        let (x_points, y_points) = (
+            vec![Fq::from(0), Fq::from(1), Fq::from(2)],
+            vec![Fq::from(8), Fq::from(10), Fq::from(16)],
+        );
+
+        let poly = UnivariatePolynomial::interpolate(&x_points, &y_points);
+
+        assert_eq!(poly.find_failing_point(&x_points, &y_points), None);
+
+        // 8 + 2x^2 corrupted to 8 + 3x^2 still passes through (0, 8)
+        let corrupted_poly = UnivariatePolynomial::new(vec![Fq::from(8), Fq::from(0), Fq::from(3)]);
+
+        assert_eq!(
+            corrupted_poly.find_failing_point(&x_points, &y_points),
+            Some((1, Fq::from(10), Fq::from(11)))
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    pub fn test_to_evaluations_subgroup() {
+        use ark_bn254::Fr;
+        use ark_ff::Field;
+
+        // 4x^3 + 3x^2 + 2x + 1
+        let poly =
+            UnivariatePolynomial::new(vec![Fr::from(1), Fr::from(2), Fr::from(3), Fr::from(4)]);
+
+        let root_of_unity = Fr::get_root_of_unity(8).unwrap();
+        let domain = (0..8u64)
+            .map(|power| root_of_unity.pow([power]))
+            .collect::<Vec<_>>();
+
+        let evaluations = poly.to_evaluations(&domain);
+
+        assert_eq!(evaluations[0], Fr::from(10));
+        assert_eq!(poly.to_evaluations_subgroup(8), evaluations);
+
+        // a subgroup smaller than the polynomial's degree still matches evaluating on it directly
+        let small_domain = (0..2u64)
+            .map(|power| Fr::get_root_of_unity(2).unwrap().pow([power]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            poly.to_evaluations_subgroup(2),
+            poly.to_evaluations(&small_domain)
+        );
+    }
+
+    #[test]
+    pub fn test_shift() {
+        use ark_bn254::Fr;
+        use ark_ff::UniformRand;
+
+        // x^2 shifted by 1 is x^2 + 2x + 1
+        let x_squared = UnivariatePolynomial::new(vec![Fr::from(0), Fr::from(0), Fr::from(1)]);
+
+        assert_eq!(
+            x_squared.shift(Fr::from(1)).coefficients,
+            vec![Fr::from(1), Fr::from(2), Fr::from(1)]
+        );
+
+        let poly = UnivariatePolynomial::new(vec![
+            Fr::from(7),
+            Fr::from(0),
+            Fr::from(3),
+            Fr::from(11),
+            Fr::from(5),
+        ]);
+
+        assert_eq!(poly.shift(Fr::from(0)).coefficients, poly.coefficients);
+
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..10 {
+            let (x, c) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+
+            assert_eq!(poly.shift(c).evaluate(x), poly.evaluate(x + c));
+        }
+    }
+
+    #[test]
+    pub fn test_pad_to() {
+        let poly = UnivariatePolynomial::new(vec![Fq::from(1), Fq::from(2), Fq::from(3)]);
+        let padded_poly = poly.pad_to(8);
+
+        assert_eq!(padded_poly.coefficients.len(), 8);
+        assert_eq!(padded_poly.coefficients[..3], poly.coefficients);
+
+        for x in [0, 1, 2, 5, 17] {
+            assert_eq!(
+                padded_poly.evaluate(Fq::from(x)),
+                poly.evaluate(Fq::from(x))
+            );
+        }
+
+        assert_eq!(poly.pad_to(3), poly);
+
+        print_summary!();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot pad a polynomial with 3 coefficients to length 2")]
+    pub fn test_pad_to_shorter_length() {
+        UnivariatePolynomial::new(vec![Fq::from(1), Fq::from(2), Fq::from(3)]).pad_to(2);
+    }
 }
@@ -1,4 +1,6 @@
-use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
+use polynomials::univariate_polynomial::dense_coefficient_form::{
+    has_duplicates, UnivariatePolynomial,
+};
 
 use ark_ff::PrimeField;
 use rand::Rng;
@@ -97,6 +99,11 @@ impl<T: PrimeField> ShamierProtocol<T> {
                     )
                 });
 
+        // two shares at the same x point can't be interpolated through
+        if has_duplicates(&x_points).is_some() {
+            return Err("Shares must have distinct x points");
+        }
+
         // Get back the polynomial we got while generating the shares
         let original_polynomial = UnivariatePolynomial::interpolate(&x_points, &y_points);
 
@@ -150,6 +157,19 @@ mod test {
         assert_ne!(secret, Ok(Fq::from(62)));
     }
 
+    #[test]
+    pub fn test_reconstruct_secret_duplicate_x_points() {
+        let shamier = ShamierProtocol::new(3, 7, Fq::from(4));
+
+        let secret = shamier.reconstruct_secret(&[
+            (Fq::from(1), Fq::from(15)),
+            (Fq::from(2), Fq::from(91)),
+            (Fq::from(1), Fq::from(15)),
+        ]);
+
+        assert_eq!(secret, Err("Shares must have distinct x points"));
+    }
+
     #[test]
     pub fn test_reconstruct_secret_enough_and_right() {
         // [(0, 84), (1, 33), (2, 14592161914559516814830937163504850059130874104865215775126025263096817472401), (3, 22), (5, 14592161914559516814830937163504850059130874104865215775126025263096817472521), (6, 233)]
@@ -1,5 +1,8 @@
-use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
+use polynomials::univariate_polynomial::dense_coefficient_form::{
+    has_duplicates, UnivariatePolynomial,
+};
 
+use ark_ec::PrimeGroup;
 use ark_ff::PrimeField;
 use rand::Rng;
 use std::marker::PhantomData;
@@ -16,6 +19,9 @@ use std::marker::PhantomData;
 // We can just use other random points with the secret as evaluation points of the polynomial.
 // The secret will just be made the first evaluation point
 
+// The password shares, alongside each share's x point with its public share g^f(x)
+pub type SharesWithCommitments<T, G> = (Vec<(T, T)>, Vec<(T, G)>);
+
 pub struct ShamirProtocol<T: PrimeField> {
     _marker: PhantomData<T>,
     quorom: u32,
@@ -52,6 +58,36 @@ impl<T: PrimeField> ShamirProtocol<T> {
             .collect()
     }
 
+    // Generate the password shares alongside a public share g^f(x) for each of them.
+    // The public shares let anyone check a share without learning it, and g^secret can be gotten back
+    // by interpolating the public shares in the exponent (useful for threshold ElGamal-style setups).
+    pub fn generate_shares_with_commitment<G: PrimeGroup<ScalarField = T>>(
+        &self,
+        secret: &T,
+    ) -> SharesWithCommitments<T, G> {
+        let shares = self.generate_shares(secret);
+
+        let commitments = shares
+            .iter()
+            .map(|(x, y)| (*x, G::generator().mul_bigint(y.into_bigint())))
+            .collect();
+
+        (shares, commitments)
+    }
+
+    // Check that a share matches the public share committed to at the same x point
+    pub fn verify_commitment<G: PrimeGroup<ScalarField = T>>(
+        share: &(T, T),
+        commitments: &[(T, G)],
+    ) -> bool {
+        match commitments.iter().find(|(x, _)| *x == share.0) {
+            Some((_, public_share)) => {
+                G::generator().mul_bigint(share.1.into_bigint()) == *public_share
+            }
+            None => false,
+        }
+    }
+
     // Verify that the shares given to reconstruct a secret is up to the quorom
     fn verify_shares(&self, shares: &[(T, T)]) -> bool {
         shares.len() >= self.quorom as usize
@@ -82,17 +118,89 @@ impl<T: PrimeField> ShamirProtocol<T> {
                     )
                 });
 
+        // two shares at the same x point can't be interpolated through
+        if has_duplicates(&x_points).is_some() {
+            return Err("Shares must have distinct x points");
+        }
+
         // Get back the polynomial we got while generating the shares
         let original_polynomial = UnivariatePolynomial::interpolate(&x_points, &y_points);
 
         // Evaluate the polynomial at secret's x_point (0 in this case)
         Ok(original_polynomial.evaluate(T::from(0)))
     }
+
+    // Adds two dealers' share sets together, share by share
+    // Both sets must be at the same x points (in the same order). Shamir sharing is linear, so the summed shares
+    // are shares of the polynomial f_a + f_b, and reconstructing them gives secret_a + secret_b
+    pub fn add_shares(a: &[(T, T)], b: &[(T, T)]) -> Vec<(T, T)> {
+        if a.len() != b.len() {
+            panic!("share sets must have the same length");
+        }
+
+        a.iter()
+            .zip(b)
+            .map(|((x_a, y_a), (x_b, y_b))| {
+                if x_a != x_b {
+                    panic!("shares must be at identical x points");
+                }
+
+                (*x_a, *y_a + y_b)
+            })
+            .collect()
+    }
+
+    // Barycentric weights for the given x points: w_i = 1 / ∏(x_i - x_j) over j != i
+    // These only depend on the x points, so when many secrets are shared at the same x points (batch reconstruction)
+    // they can be computed once and reused with reconstruct_with_weights
+    pub fn precompute_weights(xs: &[T]) -> Vec<T> {
+        xs.iter()
+            .enumerate()
+            .map(|(i, x_i)| {
+                xs.iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .fold(T::one(), |acc, (_, x_j)| acc * (*x_i - x_j))
+                    .inverse()
+                    .expect("x points must be distinct")
+            })
+            .collect()
+    }
+
+    // Evaluates the polynomial through (xs, ys) at the given point using precomputed weights (at = 0 gives back the secret)
+    // f(at) = ∏(at - x_j) * ∑ w_i * y_i / (at - x_i)
+    pub fn reconstruct_with_weights(
+        xs: &[T],
+        ys: &[T],
+        weights: &[T],
+        at: T,
+    ) -> Result<T, &'static str> {
+        if xs.len() != ys.len() || xs.len() != weights.len() {
+            return Err("X points, y points and weights must have the same length");
+        }
+
+        // the point is one of the shares, so there is nothing to interpolate
+        if let Some(idx) = xs.iter().position(|x| *x == at) {
+            return Ok(ys[idx]);
+        }
+
+        let numerator = xs.iter().fold(T::one(), |acc, x| acc * (at - x));
+
+        let weighted_sum: T = xs
+            .iter()
+            .zip(ys)
+            .zip(weights)
+            .map(|((x, y), weight)| *weight * y / (at - x))
+            .sum();
+
+        Ok(numerator * weighted_sum)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use ark_bls12_381::{Fr, G1Projective};
     use ark_bn254::Fq;
 
     #[test]
@@ -133,6 +241,19 @@ mod test {
         assert_ne!(secret, Ok(Fq::from(62)));
     }
 
+    #[test]
+    pub fn test_reconstruct_secret_duplicate_x_points() {
+        let shamir = ShamirProtocol::new(3, 7);
+
+        let secret = shamir.reconstruct_secret(&[
+            (Fq::from(1), Fq::from(15)),
+            (Fq::from(2), Fq::from(91)),
+            (Fq::from(1), Fq::from(15)),
+        ]);
+
+        assert_eq!(secret, Err("Shares must have distinct x points"));
+    }
+
     #[test]
     pub fn test_reconstruct_secret_enough_and_right() {
         let secret = Fq::from(62);
@@ -144,4 +265,164 @@ mod test {
 
         assert_eq!(regenerated_secret, Ok(secret));
     }
+
+    #[test]
+    pub fn test_generate_shares_with_commitment() {
+        let secret = Fr::from(62);
+        let shamir = ShamirProtocol::new(3, 7);
+
+        let (shares, commitments) = shamir.generate_shares_with_commitment::<G1Projective>(&secret);
+
+        assert!(shares
+            .iter()
+            .all(|share| ShamirProtocol::verify_commitment(share, &commitments)));
+        assert!(!ShamirProtocol::verify_commitment(
+            &(shares[0].0, shares[0].1 + Fr::from(1)),
+            &commitments
+        ));
+
+        // interpolate the public shares in the exponent at x = 0 to get back g^secret
+        let public_shares = &commitments[0..3];
+        let public_secret: G1Projective = public_shares
+            .iter()
+            .enumerate()
+            .map(|(i, (x_i, public_share))| {
+                let lagrange_coefficient = public_shares
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .fold(Fr::from(1), |acc, (_, (x_j, _))| {
+                        acc * (*x_j / (*x_j - x_i))
+                    });
+
+                public_share.mul_bigint(lagrange_coefficient.into_bigint())
+            })
+            .sum();
+
+        assert_eq!(
+            public_secret,
+            G1Projective::generator().mul_bigint(secret.into_bigint())
+        );
+    }
+
+    #[test]
+    pub fn test_add_shares() {
+        let shamir = ShamirProtocol::new(3, 5);
+        let (secret_a, secret_b) = (Fq::from(62), Fq::from(38));
+
+        // the second dealer shares at the first one's x points
+        let shares_a = shamir.generate_shares(&secret_a);
+        let polynomial_b = UnivariatePolynomial::new(vec![secret_b, Fq::from(4), Fq::from(9)]);
+        let shares_b = shares_a
+            .iter()
+            .map(|(x, _)| (*x, polynomial_b.evaluate(*x)))
+            .collect::<Vec<_>>();
+
+        let summed_shares = ShamirProtocol::add_shares(&shares_a, &shares_b);
+
+        assert_eq!(
+            shamir.reconstruct_secret(&summed_shares),
+            Ok(secret_a + secret_b)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "shares must be at identical x points")]
+    pub fn test_add_shares_at_different_x_points() {
+        let shamir = ShamirProtocol::new(3, 5);
+
+        ShamirProtocol::add_shares(
+            &shamir.generate_shares(&Fq::from(62)),
+            &shamir.generate_shares(&Fq::from(38)),
+        );
+    }
+
+    #[test]
+    pub fn test_reconstruct_with_weights() {
+        let shamir = ShamirProtocol::new(4, 4);
+        let secrets = (1..=5u64).map(Fq::from).collect::<Vec<_>>();
+
+        // share every secret, then move them all onto the first one's x points
+        // (re-evaluating each secret's polynomial there), so they can share weights
+        let shares = shamir.generate_shares(&secrets[0]);
+        let xs = shares.iter().map(|(x, _)| *x).collect::<Vec<_>>();
+        let weights = ShamirProtocol::precompute_weights(&xs);
+
+        for secret in &secrets {
+            let polynomial =
+                UnivariatePolynomial::new(vec![*secret, Fq::from(3), Fq::from(5), Fq::from(7)]);
+            let shares = xs
+                .iter()
+                .map(|x| (*x, polynomial.evaluate(*x)))
+                .collect::<Vec<_>>();
+            let ys = shares.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+
+            let reconstructed =
+                ShamirProtocol::reconstruct_with_weights(&xs, &ys, &weights, Fq::from(0));
+
+            assert_eq!(reconstructed, Ok(*secret));
+            assert_eq!(reconstructed, shamir.reconstruct_secret(&shares));
+
+            // any other point on the polynomial comes back too
+            assert_eq!(
+                ShamirProtocol::reconstruct_with_weights(&xs, &ys, &weights, Fq::from(9)),
+                Ok(polynomial.evaluate(Fq::from(9)))
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_reconstruct_with_weights_length_mismatch() {
+        let xs = vec![Fq::from(1), Fq::from(2), Fq::from(3)];
+        let weights = ShamirProtocol::precompute_weights(&xs);
+
+        assert_eq!(
+            ShamirProtocol::reconstruct_with_weights(
+                &xs,
+                &[Fq::from(4), Fq::from(5)],
+                &weights,
+                Fq::from(0)
+            ),
+            Err("X points, y points and weights must have the same length")
+        );
+        assert_eq!(
+            ShamirProtocol::reconstruct_with_weights(
+                &xs,
+                &[Fq::from(4), Fq::from(5), Fq::from(6)],
+                &weights[1..],
+                Fq::from(0)
+            ),
+            Err("X points, y points and weights must have the same length")
+        );
+    }
+
+    #[test]
+    pub fn test_reconstruct_with_weights_matches_per_call_for_batches() {
+        let shamir = ShamirProtocol::new(16, 16);
+        let xs = shamir
+            .generate_shares(&Fq::from(0))
+            .iter()
+            .map(|(x, _)| *x)
+            .collect::<Vec<_>>();
+        let weights = ShamirProtocol::precompute_weights(&xs);
+
+        // a batch of secrets shared at the same x points, all reconstructed with the one set of weights
+        for secret in (0..20u64).map(|secret| Fq::from(secret * 31 + 7)) {
+            let polynomial = UnivariatePolynomial::new(
+                std::iter::once(secret)
+                    .chain((1..16u64).map(|coefficient| Fq::from(coefficient) * secret))
+                    .collect(),
+            );
+            let shares = xs
+                .iter()
+                .map(|x| (*x, polynomial.evaluate(*x)))
+                .collect::<Vec<_>>();
+            let ys = shares.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+
+            assert_eq!(
+                ShamirProtocol::reconstruct_with_weights(&xs, &ys, &weights, Fq::from(0)),
+                shamir.reconstruct_secret(&shares)
+            );
+        }
+    }
 }
@@ -45,7 +45,8 @@ impl<T: PrimeField> SumcheckProver<T> {
     fn generate_round_polys(
         initial_polynomial: &ComposedPolynomial<T>,
         transcript: &mut Transcript<T>,
-    ) -> (Vec<UnivariatePolynomial<T>>, Vec<T>) {
+    ) -> (Vec<UnivariatePolynomial<T>>, Vec<T>, Vec<T>) {
+        let mut claimed_sums = Vec::new();
         let (
             mut resulting_multi_polynomial,
             mut resulting_sum_polynomial,
@@ -71,7 +72,7 @@ impl<T: PrimeField> SumcheckProver<T> {
 
         // The steps for generating the univariate round polys differ based on the type of initial polynomial
         // => In the case of a sum polynomial,
-        //    - We get the degree of the sum polynomial and partially evaluate the variable of concern at d+1 points.
+        //    - We get the per variable degree d of the sum polynomial (its most factors in a product) and evaluate the variable of concern at d+1 points.
         //    - We then reduce and sum at each step to get single evaluation points at which we interpolate at to get a univariate.
 
         // => In the case of a regular Multilinear poly,
@@ -87,40 +88,21 @@ impl<T: PrimeField> SumcheckProver<T> {
             );
 
             if let Some(sum_poly) = &resulting_sum_polynomial {
-                let degree = sum_poly.degree();
-                let mut evaluation_points = vec![T::from(0); degree + 1];
-
-                for i in 0..degree + 1 {
-                    let mut _points = vec![None; sum_poly.number_of_variables() as usize];
-                    _points[0] = Some(T::from(i as u8));
-
-                    let res = sum_poly.partial_evaluate(&_points).reduce();
-
-                    evaluation_points[i] = res.iter().sum();
-                }
+                let evaluation_points = sum_poly.first_variable_sums();
 
                 claimed_sum = evaluation_points[0] + evaluation_points[1];
 
-                evaluated_polynomial_over_boolean_hypercube = UnivariatePolynomial::interpolate(
-                    &vec![T::from(0), T::from(1), T::from(2)],
-                    &evaluation_points,
-                );
+                evaluated_polynomial_over_boolean_hypercube =
+                    UnivariatePolynomial::from_hypercube_evaluations(&evaluation_points);
             } else if let Some(multi_poly) = &resulting_multi_polynomial {
-                let evaluation_points = multi_poly.get_evaluation_points();
-                let (first_half, second_half) =
-                    evaluation_points.split_at(evaluation_points.len() / 2);
+                let (low_branch, high_branch) = multi_poly.split_first_variable();
 
-                let (eval_0, eval_1) = (
-                    T::from(first_half.iter().sum::<T>()),
-                    T::from(second_half.iter().sum::<T>()),
-                );
+                let (eval_0, eval_1) = (low_branch.evaluation_sum(), high_branch.evaluation_sum());
 
                 claimed_sum = eval_0 + eval_1;
 
-                evaluated_polynomial_over_boolean_hypercube = UnivariatePolynomial::interpolate(
-                    &vec![T::from(0), T::from(1)],
-                    &vec![eval_0, eval_1],
-                )
+                evaluated_polynomial_over_boolean_hypercube =
+                    UnivariatePolynomial::from_hypercube_evaluations(&[eval_0, eval_1])
             }
 
             transcript.append_n(&[
@@ -148,20 +130,21 @@ impl<T: PrimeField> SumcheckProver<T> {
                 resulting_multi_polynomial = Some(multi_poly.evaluate(&points));
             }
 
+            claimed_sums.push(claimed_sum);
             round_polys.push(evaluated_polynomial_over_boolean_hypercube);
         });
 
-        (round_polys, random_challenges)
+        (round_polys, random_challenges, claimed_sums)
     }
 
     // This creates a sum check proof, with the round_polys generated and an initial claim sum
     pub fn generate_sumcheck_proof(init_polynomial: &MultiLinearPolynomial<T>) -> SumCheckProof<T> {
-        let mut transcript = Transcript::new();
+        let mut transcript = Transcript::with_domain(b"SUMCHECK");
 
         // append initial polynomial to transcript to initiate process
         transcript.append(&init_polynomial.to_bytes());
 
-        let (round_polys, _) = Self::generate_round_polys(
+        let (round_polys, _, _) = Self::generate_round_polys(
             &ComposedPolynomial::MultilinearPolynomial(init_polynomial.clone()),
             &mut transcript,
         );
@@ -177,7 +160,25 @@ impl<T: PrimeField> SumcheckProver<T> {
         init_poly: SumPolynomial<T>,
         transcript: &mut Transcript<T>,
     ) -> (SumCheckProof<T>, Vec<T>) {
-        let (round_polys, random_points) =
+        let (sum_check_proof, random_points, _) =
+            Self::generate_proof_for_partial_verify_with_claims(
+                initial_claim_sum,
+                init_poly,
+                transcript,
+            );
+
+        (sum_check_proof, random_points)
+    }
+
+    // Same as generate_proof_for_partial_verify, but also returns the claimed sum the prover computed in every round.
+    // Round i's claim is the verifier's incoming claim for that round (see SumcheckVerifier::reduction_trace),
+    // which makes it easy to find the first round where the prover and verifier claim chains diverge
+    pub fn generate_proof_for_partial_verify_with_claims(
+        initial_claim_sum: T,
+        init_poly: SumPolynomial<T>,
+        transcript: &mut Transcript<T>,
+    ) -> (SumCheckProof<T>, Vec<T>, Vec<T>) {
+        let (round_polys, random_points, claimed_sums) =
             Self::generate_round_polys(&ComposedPolynomial::SumPolynomial(init_poly), transcript);
 
         (
@@ -186,6 +187,7 @@ impl<T: PrimeField> SumcheckProver<T> {
                 round_polys,
             },
             random_points,
+            claimed_sums,
         )
     }
 }
@@ -1,18 +1,64 @@
+use fiat_shamir::transcript::Transcript;
 use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
 
-use ark_ff::PrimeField;
+use ark_ff::{BigInteger, PrimeField};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct SumCheckProof<T: PrimeField> {
     pub initial_claim_sum: T,
     pub round_polys: Vec<UnivariatePolynomial<T>>,
 }
 
+impl<T: PrimeField> SumCheckProof<T> {
+    // Returns the chain of claimed sums [s0, s1, ...] across the rounds, given the challenge of each round.
+    // s0 is the initial claim sum and s_{i+1} is round poly i evaluated at challenge i.
+    // The chain stops at the first round poly whose sum over {0, 1} doesn't match the current claim.
+    pub fn claimed_sum_chain(&self, challenges: &[T]) -> Vec<T> {
+        if challenges.len() != self.round_polys.len() {
+            panic!(
+                "Expected {} challenges, got {}",
+                self.round_polys.len(),
+                challenges.len()
+            );
+        }
+
+        let mut chain = vec![self.initial_claim_sum];
+
+        for (round_poly, challenge) in self.round_polys.iter().zip(challenges) {
+            if round_poly.evaluate_sum_over_boolean_hypercube() != *chain.last().unwrap() {
+                break;
+            }
+
+            chain.push(round_poly.evaluate(*challenge));
+        }
+
+        chain
+    }
+}
+
+// Lets a parent protocol bind a whole sumcheck proof into its transcript without replaying the verifier.
+// This lives here rather than on Transcript itself, since fiat_shamir can't depend on sumcheck.
+pub trait SumcheckTranscript<T: PrimeField> {
+    fn append_sumcheck_proof(&mut self, proof: &SumCheckProof<T>);
+}
+
+impl<T: PrimeField> SumcheckTranscript<T> for Transcript<T> {
+    // absorb the initial claim, then every round poly with the number of rounds and coefficients as length prefixes
+    fn append_sumcheck_proof(&mut self, proof: &SumCheckProof<T>) {
+        self.append(&proof.initial_claim_sum.into_bigint().to_bytes_le());
+        self.append_u64(proof.round_polys.len() as u64);
+
+        for round_poly in &proof.round_polys {
+            self.append_u64(round_poly.coefficients.len() as u64);
+            self.append(&round_poly.to_bytes());
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
-    use fiat_shamir::transcript::Transcript;
     use field_tracker::{print_summary, Ft};
     use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
     use polynomials::product_polynomial::ProductPolynomial;
@@ -23,6 +69,46 @@ mod test {
 
     type Fq = Ft!(ark_bn254::Fq);
 
+    // Runs the full and partial sumcheck over the given field, so regressions tied to a specific modulus are caught
+    fn run_sumcheck_over_field<T: PrimeField>() {
+        let polynomial = MultiLinearPolynomial::new(
+            &[0, 0, 0, 3, 0, 0, 2, 5]
+                .into_iter()
+                .map(T::from)
+                .collect::<Vec<T>>(),
+        );
+
+        let sum_check_proof = SumcheckProver::generate_sumcheck_proof(&polynomial);
+
+        assert!(SumcheckVerifier::verify_proof(&polynomial, sum_check_proof));
+
+        let mut wrong_sum_check_proof = SumcheckProver::generate_sumcheck_proof(&polynomial);
+        wrong_sum_check_proof.initial_claim_sum += T::one();
+
+        assert!(!SumcheckVerifier::verify_proof(
+            &polynomial,
+            wrong_sum_check_proof
+        ));
+
+        let (eval_1, eval_2) = (
+            MultiLinearPolynomial::new(&vec![T::zero(), T::zero(), T::zero(), T::from(2u64)]),
+            MultiLinearPolynomial::new(&vec![T::zero(), T::zero(), T::zero(), T::from(3u64)]),
+        );
+
+        let sum_polynomial = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![eval_1.clone(), eval_2.clone()]),
+            ProductPolynomial::new(vec![eval_1, eval_2]),
+        ]);
+
+        let (sum_check_proof, _) = SumcheckProver::generate_proof_for_partial_verify(
+            T::from(12u64),
+            sum_polynomial,
+            &mut Transcript::new(),
+        );
+
+        assert!(SumcheckVerifier::partial_verify(&sum_check_proof, &mut Transcript::new(), 2).0);
+    }
+
     #[test]
     fn test_full_sumcheck_pass() {
         let polynomial = vec![
@@ -81,6 +167,47 @@ mod test {
         print_summary!();
     }
 
+    #[test]
+    fn test_verify_many_sumcheck_proofs() {
+        let polynomials = [
+            MultiLinearPolynomial::new(&vec![
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(3),
+                Fq::from(0),
+                Fq::from(0),
+                Fq::from(2),
+                Fq::from(5),
+            ]),
+            MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(2), Fq::from(3), Fq::from(4)]),
+            MultiLinearPolynomial::new(&vec![Fq::from(7), Fq::from(9)]),
+        ];
+
+        let mut claims = polynomials
+            .iter()
+            .map(|polynomial| {
+                (
+                    polynomial.clone(),
+                    SumcheckProver::generate_sumcheck_proof(polynomial),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert!(SumcheckVerifier::verify_many(&claims));
+
+        // tamper with the claim of the second proof
+        claims[1].1.initial_claim_sum = Fq::from(11);
+
+        assert!(!SumcheckVerifier::verify_many(&claims));
+        assert_eq!(
+            SumcheckVerifier::verify_many_detailed(&claims),
+            vec![true, false, true]
+        );
+
+        print_summary!();
+    }
+
     #[test]
     fn test_partial_sumcheck_pass() {
         let (eval_1, eval_2) = (
@@ -105,8 +232,304 @@ mod test {
             &mut Transcript::new(),
         );
 
-        assert!(SumcheckVerifier::partial_verify(&sum_check_proof, &mut Transcript::new()).0);
+        assert!(SumcheckVerifier::partial_verify(&sum_check_proof, &mut Transcript::new(), 2).0);
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_append_sumcheck_proof() {
+        let (first_polynomial, second_polynomial) = (
+            MultiLinearPolynomial::new(&vec![Fq::from(0), Fq::from(3), Fq::from(2), Fq::from(5)]),
+            MultiLinearPolynomial::new(&vec![Fq::from(1), Fq::from(3), Fq::from(2), Fq::from(5)]),
+        );
+
+        let first_proof = SumcheckProver::generate_sumcheck_proof(&first_polynomial);
+        let second_proof = SumcheckProver::generate_sumcheck_proof(&second_polynomial);
+
+        let absorb_and_sample = |proof: &SumCheckProof<Fq>| {
+            let mut transcript = Transcript::new();
+            transcript.append_sumcheck_proof(proof);
+
+            transcript.sample_challenge()
+        };
+
+        assert_eq!(
+            absorb_and_sample(&first_proof),
+            absorb_and_sample(&SumcheckProver::generate_sumcheck_proof(&first_polynomial))
+        );
+        assert_ne!(
+            absorb_and_sample(&first_proof),
+            absorb_and_sample(&second_proof)
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_perform_oracle_check_with_closure() {
+        let polynomial = MultiLinearPolynomial::new(&vec![
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(3),
+            Fq::from(0),
+            Fq::from(0),
+            Fq::from(2),
+            Fq::from(5),
+        ]);
+        let challenges = vec![Some(Fq::from(3)), Some(Fq::from(7)), Some(Fq::from(11))];
+
+        let oracle = |point: &[Fq]| {
+            polynomial
+                .evaluate(&point.iter().map(|r| Some(*r)).collect::<Vec<_>>())
+                .get_evaluation_points()[0]
+        };
+
+        let final_claim_sum = polynomial.evaluate(&challenges).get_evaluation_points()[0];
+
+        for claim in [final_claim_sum, final_claim_sum + Fq::from(1)] {
+            assert_eq!(
+                SumcheckVerifier::perform_oracle_check_with(oracle, &challenges, &claim),
+                SumcheckVerifier::perform_oracle_check(&polynomial, &challenges, &claim)
+            );
+        }
+
+        assert!(SumcheckVerifier::perform_oracle_check_with(
+            oracle,
+            &challenges,
+            &final_claim_sum
+        ));
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_claimed_sum_chain() {
+        let (eval_1, eval_2) = (
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(2)],
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(3)],
+        );
+
+        let initial_polynomial = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+        ]);
+
+        let (sum_check_proof, challenges) = SumcheckProver::generate_proof_for_partial_verify(
+            Fq::from(12),
+            initial_polynomial,
+            &mut Transcript::new(),
+        );
+
+        let chain = sum_check_proof.claimed_sum_chain(&challenges);
+
+        assert_eq!(chain.len(), sum_check_proof.round_polys.len() + 1);
+        assert_eq!(chain[0], Fq::from(12));
+
+        // each claim is the hypercube sum of the next round poly, and the next claim is that poly at the challenge
+        for (idx, round_poly) in sum_check_proof.round_polys.iter().enumerate() {
+            assert_eq!(round_poly.evaluate_sum_over_boolean_hypercube(), chain[idx]);
+            assert_eq!(round_poly.evaluate(challenges[idx]), chain[idx + 1]);
+        }
+
+        // the last claim matches the final claim sum from the partial verifier
+        let (is_verified, final_claim_sum, _) =
+            SumcheckVerifier::partial_verify(&sum_check_proof, &mut Transcript::new(), 2);
+
+        assert!(is_verified);
+        assert_eq!(*chain.last().unwrap(), final_claim_sum);
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_reduction_trace() {
+        let (eval_1, eval_2) = (
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(2)],
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(3)],
+        );
+
+        let initial_polynomial = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+        ]);
+
+        let (sum_check_proof, _) = SumcheckProver::generate_proof_for_partial_verify(
+            Fq::from(12),
+            initial_polynomial,
+            &mut Transcript::new(),
+        );
+
+        let trace = SumcheckVerifier::reduction_trace(&sum_check_proof, &mut Transcript::new());
+
+        assert_eq!(trace.len(), sum_check_proof.round_polys.len());
+        assert_eq!(trace[0].0, Fq::from(12));
+
+        // every round's incoming claim is the hypercube sum of its poly, and the previous round's outgoing claim
+        for (idx, (incoming_claim, round_poly, _)) in trace.iter().enumerate() {
+            assert_eq!(
+                round_poly.evaluate_sum_over_boolean_hypercube(),
+                *incoming_claim
+            );
+
+            if idx > 0 {
+                assert_eq!(trace[idx - 1].2, *incoming_claim);
+            }
+        }
+
+        let (is_verified, final_claim_sum, _) =
+            SumcheckVerifier::partial_verify(&sum_check_proof, &mut Transcript::new(), 2);
+
+        assert!(is_verified);
+        assert_eq!(trace.last().unwrap().2, final_claim_sum);
 
         print_summary!();
     }
+
+    #[test]
+    fn test_prover_claims_match_reduction_trace() {
+        let (eval_1, eval_2) = (
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(2)],
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(3)],
+        );
+
+        let initial_polynomial = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+        ]);
+
+        let (sum_check_proof, _, claimed_sums) =
+            SumcheckProver::generate_proof_for_partial_verify_with_claims(
+                Fq::from(12),
+                initial_polynomial,
+                &mut Transcript::new(),
+            );
+
+        let trace = SumcheckVerifier::reduction_trace(&sum_check_proof, &mut Transcript::new());
+
+        assert_eq!(claimed_sums.len(), trace.len());
+        assert_eq!(claimed_sums[0], Fq::from(12));
+
+        // the prover's claim for every round is the verifier's incoming claim,
+        // and every round after the first claims the previous round's outgoing claim
+        for (idx, (incoming_claim, _, outgoing_claim)) in trace.iter().enumerate() {
+            assert_eq!(claimed_sums[idx], *incoming_claim);
+
+            if idx + 1 < claimed_sums.len() {
+                assert_eq!(claimed_sums[idx + 1], *outgoing_claim);
+            }
+        }
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_partial_sumcheck_rejects_extra_rounds() {
+        let (eval_1, eval_2) = (
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(2)],
+            vec![Fq::from(0), Fq::from(0), Fq::from(0), Fq::from(3)],
+        );
+
+        let initial_polynomial = SumPolynomial::new(vec![
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+            ProductPolynomial::new(vec![
+                MultiLinearPolynomial::new(&eval_1),
+                MultiLinearPolynomial::new(&eval_2),
+            ]),
+        ]);
+
+        let (mut sum_check_proof, _) = SumcheckProver::generate_proof_for_partial_verify(
+            Fq::from(12),
+            initial_polynomial,
+            &mut Transcript::new(),
+        );
+
+        // pad the proof with bogus rounds, well past the 2 variables being summed over
+        let bogus_round_poly = sum_check_proof.round_polys[1].clone();
+        sum_check_proof
+            .round_polys
+            .extend(vec![bogus_round_poly; 1000]);
+
+        let mut transcript = Transcript::new();
+        let (is_verified, _, challenges) =
+            SumcheckVerifier::partial_verify(&sum_check_proof, &mut transcript, 2);
+
+        // rejected up front: no round was processed, so no challenge was drawn
+        assert!(!is_verified);
+        assert!(challenges.is_empty());
+        assert_eq!(
+            transcript.sample_challenge(),
+            Transcript::<Fq>::new().sample_challenge()
+        );
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_partial_sumcheck_degree_3() {
+        let evals = vec![Fq::from(1), Fq::from(2), Fq::from(3), Fq::from(4)];
+
+        let initial_polynomial = SumPolynomial::new(vec![ProductPolynomial::new(vec![
+            MultiLinearPolynomial::new(&evals),
+            MultiLinearPolynomial::new(&evals),
+            MultiLinearPolynomial::new(&evals),
+        ])]);
+
+        // 1 + 8 + 27 + 64
+        let (sum_check_proof, _) = SumcheckProver::generate_proof_for_partial_verify(
+            Fq::from(100),
+            initial_polynomial,
+            &mut Transcript::new(),
+        );
+
+        // three factors make cubic round polys, interpolated from 4 points each
+        sum_check_proof
+            .round_polys
+            .iter()
+            .for_each(|round_poly| assert_eq!(round_poly.coefficients.len(), 4));
+
+        let (is_verified, _, _) =
+            SumcheckVerifier::partial_verify(&sum_check_proof, &mut Transcript::new(), 2);
+
+        assert!(is_verified);
+
+        print_summary!();
+    }
+
+    #[test]
+    fn test_sumcheck_over_bn254_fr() {
+        run_sumcheck_over_field::<ark_bn254::Fr>();
+    }
+
+    #[test]
+    fn test_sumcheck_over_bls12_381_fr() {
+        run_sumcheck_over_field::<ark_bls12_381::Fr>();
+    }
+
+    #[test]
+    fn test_sumcheck_over_bls12_377_fr() {
+        run_sumcheck_over_field::<ark_bls12_377::Fr>();
+    }
 }
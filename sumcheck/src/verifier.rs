@@ -1,5 +1,6 @@
 use fiat_shamir::transcript::Transcript;
 use polynomials::multilinear_polynomial::evaluation_form::MultiLinearPolynomial;
+use polynomials::univariate_polynomial::dense_coefficient_form::UnivariatePolynomial;
 
 use crate::sumcheck_protocol::SumCheckProof;
 
@@ -17,22 +18,49 @@ impl<T: PrimeField> SumcheckVerifier<T> {
         challenges: &[Option<T>],
         final_claim_sum: &T,
     ) -> bool {
-        initial_polynomial
-            .evaluate(challenges)
-            .get_evaluation_points()
-            .first()
-            .unwrap()
-            == final_claim_sum
+        Self::perform_oracle_check_with(
+            |point| {
+                *initial_polynomial
+                    .evaluate(&point.iter().map(|r| Some(*r)).collect::<Vec<_>>())
+                    .get_evaluation_points()
+                    .first()
+                    .unwrap()
+            },
+            challenges,
+            final_claim_sum,
+        )
+    }
+
+    // Same oracle check, for callers that can only query the polynomial's evaluation (e.g. through a commitment opening)
+    // rather than holding the polynomial itself
+    pub fn perform_oracle_check_with(
+        oracle: impl Fn(&[T]) -> T,
+        challenges: &[Option<T>],
+        final_claim_sum: &T,
+    ) -> bool {
+        let point = challenges
+            .iter()
+            .map(|challenge| challenge.unwrap())
+            .collect::<Vec<_>>();
+
+        oracle(&point) == *final_claim_sum
     }
 
     // This bit does the partial verification for a proof minus the oracle check.
+    // max_rounds is the number of variables of the polynomial being summed over: a proof with more round polys than that
+    // is rejected before any of them are looked at, so a malicious proof can't make the verifier loop over bogus rounds
     pub fn partial_verify(
         proof: &SumCheckProof<T>,
         transcript: &mut Transcript<T>,
+        max_rounds: usize,
     ) -> (bool, T, Vec<Option<T>>) {
         let mut challenges: Vec<Option<T>> = vec![];
         let mut curr_claimed_sum = proof.initial_claim_sum;
 
+        if proof.round_polys.len() > max_rounds {
+            return (false, curr_claimed_sum, challenges);
+        }
+
         // This is basically generating all the sampled values e.g(a,b,c)
         // This is done using the same hashing method that the prover used to generate them
         for evaluated_polynomial_over_boolean in &proof.round_polys {
@@ -61,11 +89,65 @@ impl<T: PrimeField> SumcheckVerifier<T> {
         (true, curr_claimed_sum, challenges)
     }
 
+    // Replays partial_verify's transcript and returns, for every round, the claimed sum going in,
+    // the round polynomial and the claim it reduces to at the round's challenge.
+    // Unlike partial_verify this doesn't stop at a failing round, so callers (e.g. a recursive verifier) check the rounds themselves
+    pub fn reduction_trace(
+        proof: &SumCheckProof<T>,
+        transcript: &mut Transcript<T>,
+    ) -> Vec<(T, UnivariatePolynomial<T>, T)> {
+        let mut curr_claimed_sum = proof.initial_claim_sum;
+
+        proof
+            .round_polys
+            .iter()
+            .map(|round_poly| {
+                transcript.append_n(&[
+                    &curr_claimed_sum.into_bigint().to_bytes_le(),
+                    &round_poly.to_bytes(),
+                ]);
+
+                let challenge = transcript.sample_challenge();
+                let incoming_claim = curr_claimed_sum;
+
+                curr_claimed_sum = round_poly.evaluate(challenge);
+
+                (incoming_claim, round_poly.clone(), curr_claimed_sum)
+            })
+            .collect()
+    }
+
     pub fn verify_proof(
         initial_polynomial: &MultiLinearPolynomial<T>,
         proof: SumCheckProof<T>,
     ) -> bool {
-        let mut transcript = Transcript::new();
+        Self::verify_proof_ref(initial_polynomial, &proof)
+    }
+
+    // Verify a batch of independent sumcheck proofs, returning true only if all of them pass.
+    pub fn verify_many(claims: &[(MultiLinearPolynomial<T>, SumCheckProof<T>)]) -> bool {
+        Self::verify_many_detailed(claims)
+            .iter()
+            .all(|is_verified| *is_verified)
+    }
+
+    // Verify a batch of independent sumcheck proofs and return the result of each one.
+    // Every proof still gets its own transcript: each transcript is seeded with its own initial polynomial,
+    // so sharing one across proofs would let challenges of one proof depend on another.
+    pub fn verify_many_detailed(
+        claims: &[(MultiLinearPolynomial<T>, SumCheckProof<T>)],
+    ) -> Vec<bool> {
+        claims
+            .iter()
+            .map(|(initial_polynomial, proof)| Self::verify_proof_ref(initial_polynomial, proof))
+            .collect()
+    }
+
+    fn verify_proof_ref(
+        initial_polynomial: &MultiLinearPolynomial<T>,
+        proof: &SumCheckProof<T>,
+    ) -> bool {
+        let mut transcript = Transcript::with_domain(b"SUMCHECK");
 
         // append initial polynomial to transcript to initiate process
         transcript.append(&initial_polynomial.to_bytes());
@@ -75,8 +157,11 @@ impl<T: PrimeField> SumcheckVerifier<T> {
             return false;
         }
 
-        let (partially_verified, final_claim_sum, challenges) =
-            Self::partial_verify(&proof, &mut transcript);
+        let (partially_verified, final_claim_sum, challenges) = Self::partial_verify(
+            proof,
+            &mut transcript,
+            initial_polynomial.number_of_variables() as usize,
+        );
 
         if !partially_verified {
             return false;
@@ -85,7 +170,7 @@ impl<T: PrimeField> SumcheckVerifier<T> {
         // if we have a last univariate polynomial variable, perform oracle check, else return false automatically
         let is_correct = match proof.round_polys.last() {
             Some(_last_univariate_poly) => {
-                Self::perform_oracle_check(&initial_polynomial, &challenges, &final_claim_sum)
+                Self::perform_oracle_check(initial_polynomial, &challenges, &final_claim_sum)
             }
             None => false,
         };